@@ -0,0 +1,665 @@
+use std::{
+    collections::VecDeque,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use trash::TrashItem;
+
+use super::{
+    exists, move_path, remove_entry,
+    types::{JournalStatus, OperationInfo, OperationKind, TransferredPath},
+    Transfer,
+};
+
+/// Operations kept for undo; older ones are dropped with their stash.
+pub const MAX_OPERATIONS: usize = 100;
+
+/// What a command did to the file system, as reported to `record`.
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// `path` did not exist before.
+    Created(PathBuf),
+    /// An entry was moved or renamed; `stash` reports what it set aside
+    /// this way.
+    Moved { from: PathBuf, to: PathBuf },
+    /// `path` was moved to the system trash.
+    Trashed(PathBuf),
+}
+
+/// Session journal of structural file operations (create, rename, move,
+/// copy, delete) with undo and redo. Deleted and overwritten entries are
+/// renamed into a stash directory rather than removed, so undoing puts them
+/// back; only entries on the stash's device can be kept that way. Trashed
+/// entries are restored from the system trash where the
+/// platform allows it. Stays unavailable until `open` is called.
+#[derive(Clone, Default)]
+pub struct FileJournalState {
+    journal: Arc<Mutex<Option<Journal>>>,
+}
+
+impl FileJournalState {
+    /// Starts an empty journal stashing into `dir`. Whatever an earlier
+    /// session left there is removed.
+    pub fn open(&self, dir: PathBuf) -> Result<(), String> {
+        match fs::remove_dir_all(&dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Failed to clear {}: {e}", dir.display())),
+        }
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create directory {}: {e}", dir.display()))?;
+        *self.journal.lock().expect("file journal poisoned") = Some(Journal {
+            stash: dir,
+            next_slot: 0,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Moves `path` into the stash instead of deleting it and returns the
+    /// change to record. `None` leaves `path` in place, to be deleted for
+    /// good: the journal is unavailable, or `path` is on another device than
+    /// the stash, where moving it would mean copying it.
+    pub fn stash(&self, path: &Path) -> Result<Option<Change>, String> {
+        self.stash_as(path, path)
+    }
+
+    /// Like `stash`, for an entry that was renamed away from `original`
+    /// first, such as the one a transfer replaced. Undo puts it back there.
+    pub fn stash_as(&self, path: &Path, original: &Path) -> Result<Option<Change>, String> {
+        // The lock is only held to reserve a slot, not for the move.
+        let (dir, slot) = {
+            let mut guard = self.journal.lock().expect("file journal poisoned");
+            let Some(journal) = guard.as_mut() else {
+                return Ok(None);
+            };
+            (journal.stash.clone(), journal.slot())
+        };
+        if !same_device(path, &dir) {
+            return Ok(None);
+        }
+        move_path(path, &slot)?;
+        Ok(Some(Change::Moved {
+            from: original.to_path_buf(),
+            to: slot,
+        }))
+    }
+
+    /// Adds an operation to undo and clears redo. `paths` are what the
+    /// operation is shown as.
+    pub fn record(&self, kind: OperationKind, paths: Vec<PathBuf>, changes: Vec<Change>) {
+        let mut guard = self.journal.lock().expect("file journal poisoned");
+        let Some(journal) = guard.as_mut() else {
+            return;
+        };
+        if changes.is_empty() {
+            return;
+        }
+        let mut steps = Vec::with_capacity(changes.len());
+        for change in changes {
+            steps.push(match change {
+                Change::Created(path) => Step::Move {
+                    from: journal.slot(),
+                    to: path,
+                },
+                Change::Moved { from, to } => Step::Move { from, to },
+                Change::Trashed(path) => match trashed_item(&path) {
+                    Some(item) => Step::Trash { path, item },
+                    // Not restorable here, so nothing before it is either.
+                    None => {
+                        journal.clear();
+                        return;
+                    }
+                },
+            });
+        }
+        journal.clear_redo();
+        journal.undo.push_back(Operation { kind, paths, steps });
+        while journal.undo.len() > MAX_OPERATIONS {
+            if let Some(dropped) = journal.undo.pop_front() {
+                journal.purge(&dropped, true);
+            }
+        }
+    }
+
+    /// Records a copy or move from what `transfer` reports, including one
+    /// that stopped halfway. Replaced entries are stashed so undo can put
+    /// them back. If one of them can't be, the operation can't be undone
+    /// whole and nothing of it is recorded. An entry that failed to stash is
+    /// left where `transfer` set it aside and named in the error.
+    pub fn record_transfer(
+        &self,
+        kind: Transfer,
+        transferred: &[TransferredPath],
+    ) -> Result<(), String> {
+        let mut changes = Vec::new();
+        let mut targets = Vec::new();
+        let mut stashed = Vec::new();
+        let mut complete = true;
+        let mut kept = Vec::new();
+        for path in transferred {
+            let Some(target) = &path.target else {
+                continue;
+            };
+            let (source, target) = (PathBuf::from(&path.source), PathBuf::from(target));
+            if source == target {
+                continue;
+            }
+            if let Some(replaced) = &path.replaced {
+                match self.stash_as(replaced, &target) {
+                    Ok(Some(change)) => {
+                        if let Change::Moved { to, .. } = &change {
+                            stashed.push(to.clone());
+                        }
+                        changes.push(change);
+                    }
+                    // Out of the stash's reach: overwritten for good.
+                    Ok(None) => {
+                        complete = false;
+                        let _ = remove_entry(replaced);
+                    }
+                    Err(e) => {
+                        complete = false;
+                        kept.push(format!(
+                            "the entry {} replaced was kept as {}: {e}",
+                            target.display(),
+                            replaced.display()
+                        ));
+                    }
+                }
+            }
+            changes.push(match kind {
+                Transfer::Copy => Change::Created(target.clone()),
+                Transfer::Move => Change::Moved {
+                    from: source,
+                    to: target.clone(),
+                },
+            });
+            targets.push(target);
+        }
+
+        if complete {
+            let operation = match kind {
+                Transfer::Copy => OperationKind::Copy,
+                Transfer::Move => OperationKind::Move,
+            };
+            self.record(operation, targets, changes);
+        } else {
+            // Without the rest of the operation the stashed entries can't
+            // be put back, so they go the way of any overwritten entry.
+            for slot in &stashed {
+                let _ = remove_entry(slot);
+            }
+            self.forget();
+        }
+        if kept.is_empty() {
+            Ok(())
+        } else {
+            Err(kept.join("; "))
+        }
+    }
+
+    /// An operation that can't be undone happened, such as deleting an entry
+    /// on another device than the stash. Earlier operations may depend on
+    /// what it changed, so the whole journal is dropped.
+    pub fn forget(&self) {
+        if let Some(journal) = self.journal.lock().expect("file journal poisoned").as_mut() {
+            journal.clear();
+        }
+    }
+
+    /// Reverts the latest operation and returns it; `None` when there is
+    /// nothing to undo. On failure the operation stays in place for a retry.
+    pub fn undo(&self) -> Result<Option<OperationInfo>, String> {
+        let mut guard = self.journal.lock().expect("file journal poisoned");
+        let Some(journal) = guard.as_mut() else {
+            return Ok(None);
+        };
+        let Some(mut operation) = journal.undo.pop_back() else {
+            return Ok(None);
+        };
+        match replay(&mut operation.steps, Direction::Undo) {
+            Ok(()) => {
+                let info = operation.info();
+                journal.redo.push(operation);
+                Ok(Some(info))
+            }
+            Err(e) => {
+                journal.undo.push_back(operation);
+                Err(e)
+            }
+        }
+    }
+
+    /// Applies the latest undone operation again and returns it.
+    pub fn redo(&self) -> Result<Option<OperationInfo>, String> {
+        let mut guard = self.journal.lock().expect("file journal poisoned");
+        let Some(journal) = guard.as_mut() else {
+            return Ok(None);
+        };
+        let Some(mut operation) = journal.redo.pop() else {
+            return Ok(None);
+        };
+        match replay(&mut operation.steps, Direction::Redo) {
+            Ok(()) => {
+                let info = operation.info();
+                journal.undo.push_back(operation);
+                Ok(Some(info))
+            }
+            Err(e) => {
+                journal.redo.push(operation);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn status(&self) -> JournalStatus {
+        let guard = self.journal.lock().expect("file journal poisoned");
+        let Some(journal) = guard.as_ref() else {
+            return JournalStatus::default();
+        };
+        JournalStatus {
+            undo: journal.undo.back().map(Operation::info),
+            redo: journal.redo.last().map(Operation::info),
+        }
+    }
+}
+
+struct Journal {
+    stash: PathBuf,
+    next_slot: u64,
+    /// Applied operations, oldest first.
+    undo: VecDeque<Operation>,
+    /// Undone operations, the next to redo last.
+    redo: Vec<Operation>,
+}
+
+impl Journal {
+    /// A fresh path in the stash. Entries that don't exist on one side of an
+    /// operation are kept there.
+    fn slot(&mut self) -> PathBuf {
+        self.next_slot += 1;
+        self.stash.join(self.next_slot.to_string())
+    }
+
+    fn clear(&mut self) {
+        for operation in std::mem::take(&mut self.undo) {
+            self.purge(&operation, true);
+        }
+        self.clear_redo();
+    }
+
+    fn clear_redo(&mut self) {
+        for operation in std::mem::take(&mut self.redo) {
+            self.purge(&operation, false);
+        }
+    }
+
+    /// Deletes what a dropped operation holds in the stash: the entries it
+    /// moved out of the way if `applied`, the entries it undid otherwise.
+    fn purge(&self, operation: &Operation, applied: bool) {
+        for step in &operation.steps {
+            if let Step::Move { from, to } = step {
+                let held = if applied { to } else { from };
+                if held.starts_with(&self.stash) && exists(held) {
+                    let _ = remove_entry(held);
+                }
+            }
+        }
+    }
+}
+
+struct Operation {
+    kind: OperationKind,
+    paths: Vec<PathBuf>,
+    /// Effects in the order they happened.
+    steps: Vec<Step>,
+}
+
+impl Operation {
+    fn info(&self) -> OperationInfo {
+        OperationInfo {
+            kind: self.kind,
+            paths: self
+                .paths
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect(),
+        }
+    }
+}
+
+enum Step {
+    /// `from` was moved to `to`. Stash slots stand in for the side where an
+    /// entry didn't exist: `from` of a created entry, `to` of a deleted one.
+    Move { from: PathBuf, to: PathBuf },
+    /// `path` went to the system trash as `item`.
+    Trash { path: PathBuf, item: TrashItem },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+impl Direction {
+    fn reverse(self) -> Self {
+        match self {
+            Direction::Undo => Direction::Redo,
+            Direction::Redo => Direction::Undo,
+        }
+    }
+}
+
+impl Step {
+    fn apply(&mut self, direction: Direction) -> Result<(), String> {
+        match (self, direction) {
+            (Step::Move { from, to }, direction) => {
+                let (source, target) = match direction {
+                    Direction::Undo => (to, from),
+                    Direction::Redo => (from, to),
+                };
+                if !exists(source) {
+                    return Err(format!("{} no longer exists", source.display()));
+                }
+                if exists(target) {
+                    return Err(format!("{} already exists", target.display()));
+                }
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent).map_err(|e| {
+                        format!("Failed to create directory {}: {e}", parent.display())
+                    })?;
+                }
+                move_path(source, target)
+            }
+            (Step::Trash { item, .. }, Direction::Undo) => restore_trashed(item),
+            (Step::Trash { path, item }, Direction::Redo) => {
+                trash::delete(&*path).map_err(|e| format!("Failed to move to trash: {e}"))?;
+                *item = trashed_item(path)
+                    .ok_or_else(|| format!("{} is not in the trash", path.display()))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Applies `steps` in `direction`: undo runs them newest first. When one
+/// fails, the ones already applied are reverted, so an operation is either
+/// undone or redone whole or left as it was.
+fn replay(steps: &mut [Step], direction: Direction) -> Result<(), String> {
+    let order: Vec<usize> = match direction {
+        Direction::Undo => (0..steps.len()).rev().collect(),
+        Direction::Redo => (0..steps.len()).collect(),
+    };
+    for (done, &index) in order.iter().enumerate() {
+        if let Err(e) = steps[index].apply(direction) {
+            for &applied in order[..done].iter().rev() {
+                let _ = steps[applied].apply(direction.reverse());
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn same_device(path: &Path, dir: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::symlink_metadata(path), fs::metadata(dir)) {
+        (Ok(entry), Ok(dir)) => entry.dev() == dir.dev(),
+        _ => false,
+    }
+}
+
+/// Same drive. A volume mounted into a folder is not told apart, so moving
+/// from one falls back to copying.
+#[cfg(not(unix))]
+fn same_device(path: &Path, dir: &Path) -> bool {
+    let prefix = |path: &Path| {
+        let canonical = path.canonicalize().ok()?;
+        canonical
+            .components()
+            .next()
+            .map(|c| c.as_os_str().to_owned())
+    };
+    let parent = path.parent().unwrap_or(path);
+    matches!((prefix(parent), prefix(dir)), (Some(a), Some(b)) if a == b)
+}
+
+/// The trash only lists and restores items on Windows and freedesktop
+/// systems.
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+fn trashed_item(path: &Path) -> Option<TrashItem> {
+    trash::os_limited::list()
+        .ok()?
+        .into_iter()
+        .filter(|item| item.original_path() == path)
+        .max_by_key(|item| item.time_deleted)
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+fn trashed_item(_path: &Path) -> Option<TrashItem> {
+    None
+}
+
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+fn restore_trashed(item: &TrashItem) -> Result<(), String> {
+    let current = trash::os_limited::list()
+        .map_err(|e| format!("Failed to read the trash: {e}"))?
+        .into_iter()
+        .find(|listed| listed.id == item.id)
+        .ok_or_else(|| {
+            format!(
+                "{} is no longer in the trash",
+                item.original_path().display()
+            )
+        })?;
+    trash::os_limited::restore_all([current])
+        .map_err(|e| format!("Failed to restore from trash: {e}"))
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+fn restore_trashed(item: &TrashItem) -> Result<(), String> {
+    Err(format!(
+        "Restoring {} from the trash is not supported on this platform",
+        item.original_path().display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_ops::{transfer, types::ConflictPolicy};
+
+    /// A fresh directory under the system temp dir, unique per test, and a
+    /// journal stashing into it.
+    fn scratch(name: &str) -> (PathBuf, FileJournalState) {
+        let dir = std::env::temp_dir().join(format!("nova-journal-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let journal = FileJournalState::default();
+        journal.open(dir.join("stash")).unwrap();
+        (dir, journal)
+    }
+
+    fn read(path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok()
+    }
+
+    #[test]
+    fn undoes_and_redoes_a_created_file() {
+        let (dir, journal) = scratch("create");
+        let file = dir.join("new.txt");
+        fs::write(&file, "new").unwrap();
+        journal.record(
+            OperationKind::Create,
+            vec![file.clone()],
+            vec![Change::Created(file.clone())],
+        );
+
+        let undone = journal.undo().unwrap().unwrap();
+        assert_eq!(undone.kind, OperationKind::Create);
+        assert!(!exists(&file));
+        assert!(journal.status().undo.is_none());
+
+        journal.redo().unwrap().unwrap();
+        assert_eq!(read(&file).as_deref(), Some("new"));
+        assert!(journal.status().redo.is_none());
+        assert!(journal.redo().unwrap().is_none());
+    }
+
+    #[test]
+    fn undoes_a_delete_from_the_stash() {
+        let (dir, journal) = scratch("delete");
+        let file = dir.join("old.txt");
+        fs::write(&file, "old").unwrap();
+        let change = journal.stash(&file).unwrap().unwrap();
+        journal.record(OperationKind::Delete, vec![file.clone()], vec![change]);
+        assert!(!exists(&file));
+
+        journal.undo().unwrap().unwrap();
+        assert_eq!(read(&file).as_deref(), Some("old"));
+        journal.redo().unwrap().unwrap();
+        assert!(!exists(&file));
+    }
+
+    #[test]
+    fn a_failed_undo_leaves_the_operation_in_place() {
+        let (dir, journal) = scratch("failed-undo");
+        let from = dir.join("a.txt");
+        let to = dir.join("b.txt");
+        fs::write(&to, "moved").unwrap();
+        journal.record(
+            OperationKind::Rename,
+            vec![to.clone()],
+            vec![Change::Moved {
+                from: from.clone(),
+                to: to.clone(),
+            }],
+        );
+        // Something else took the old name in the meantime.
+        fs::write(&from, "other").unwrap();
+
+        assert!(journal.undo().is_err());
+        assert_eq!(read(&to).as_deref(), Some("moved"));
+        assert_eq!(read(&from).as_deref(), Some("other"));
+        assert!(journal.status().undo.is_some());
+    }
+
+    #[test]
+    fn undoing_an_overwriting_move_restores_the_replaced_entry() {
+        let (dir, journal) = scratch("overwrite");
+        let (src, dst) = (dir.join("src"), dir.join("dst"));
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(src.join("a.txt"), "new").unwrap();
+        fs::write(dst.join("a.txt"), "old").unwrap();
+
+        let outcome = transfer(
+            Transfer::Move,
+            &[src.join("a.txt")],
+            &dst,
+            ConflictPolicy::Overwrite,
+            &mut |_| {},
+        );
+        assert!(outcome.error.is_none());
+        journal
+            .record_transfer(Transfer::Move, &outcome.transferred)
+            .unwrap();
+        assert_eq!(read(&dst.join("a.txt")).as_deref(), Some("new"));
+
+        journal.undo().unwrap().unwrap();
+        assert_eq!(read(&src.join("a.txt")).as_deref(), Some("new"));
+        assert_eq!(read(&dst.join("a.txt")).as_deref(), Some("old"));
+
+        journal.redo().unwrap().unwrap();
+        assert!(!exists(&src.join("a.txt")));
+        assert_eq!(read(&dst.join("a.txt")).as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn keeps_a_replaced_entry_that_fails_to_stash_and_records_nothing() {
+        let (dir, journal) = scratch("stash-error");
+        let (src, dst) = (dir.join("src"), dir.join("dst"));
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(src.join("a.txt"), "new").unwrap();
+        fs::write(dst.join("a.txt"), "old").unwrap();
+        // The first stash slot is taken by a non-empty directory, so
+        // stashing the replaced file there fails.
+        fs::create_dir_all(dir.join("stash/1/taken")).unwrap();
+
+        let outcome = transfer(
+            Transfer::Move,
+            &[src.join("a.txt")],
+            &dst,
+            ConflictPolicy::Overwrite,
+            &mut |_| {},
+        );
+        let replaced = outcome.transferred[0].replaced.clone().unwrap();
+        let error = journal
+            .record_transfer(Transfer::Move, &outcome.transferred)
+            .unwrap_err();
+
+        assert!(error.contains(&replaced.display().to_string()), "{error}");
+        assert_eq!(read(&replaced).as_deref(), Some("old"));
+        assert_eq!(read(&dst.join("a.txt")).as_deref(), Some("new"));
+        assert!(journal.status().undo.is_none());
+    }
+
+    #[test]
+    fn drops_the_oldest_operations_beyond_the_limit() {
+        let (dir, journal) = scratch("limit");
+        for i in 0..=MAX_OPERATIONS {
+            let file = dir.join(format!("{i}.txt"));
+            fs::write(&file, "x").unwrap();
+            journal.record(
+                OperationKind::Create,
+                vec![file.clone()],
+                vec![Change::Created(file)],
+            );
+        }
+        for _ in 0..MAX_OPERATIONS {
+            journal.undo().unwrap().unwrap();
+        }
+        assert!(journal.undo().unwrap().is_none());
+        assert!(exists(&dir.join("0.txt")));
+        assert!(!exists(&dir.join("1.txt")));
+    }
+}
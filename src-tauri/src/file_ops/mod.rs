@@ -0,0 +1,372 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+pub mod journal;
+pub mod types;
+
+use crate::files::atomic::temp_path;
+use types::{ConflictPolicy, TransferProgress, TransferredPath};
+
+/// Minimum time between two progress reports of one operation.
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    Copy,
+    Move,
+}
+
+/// Result of `transfer`: the sources handled before it stopped, and why it
+/// stopped early, if it did.
+#[derive(Debug, Default)]
+pub struct TransferOutcome {
+    pub transferred: Vec<TransferredPath>,
+    pub error: Option<String>,
+}
+
+/// Copies or moves `sources` into the directory `destination`. Directories
+/// are transferred recursively; symlinks are transferred as links. Moves
+/// across devices fall back to copying and deleting. A target only appears
+/// once it is complete. An entry replaced under `ConflictPolicy::Overwrite`
+/// is put back when its replacement fails, and handed to the caller in
+/// `TransferredPath::replaced` otherwise. Stops at the first failure.
+/// Progress is reported for copied files, throttled to `PROGRESS_INTERVAL`.
+pub fn transfer(
+    kind: Transfer,
+    sources: &[PathBuf],
+    destination: &Path,
+    conflict: ConflictPolicy,
+    report: &mut dyn FnMut(&TransferProgress),
+) -> TransferOutcome {
+    let mut outcome = TransferOutcome::default();
+    if let Err(e) = transfer_each(kind, sources, destination, conflict, report, &mut outcome) {
+        outcome.error = Some(e);
+    }
+    outcome
+}
+
+fn transfer_each(
+    kind: Transfer,
+    sources: &[PathBuf],
+    destination: &Path,
+    conflict: ConflictPolicy,
+    report: &mut dyn FnMut(&TransferProgress),
+    outcome: &mut TransferOutcome,
+) -> Result<(), String> {
+    if !destination.is_dir() {
+        return Err(format!("{} is not a directory", destination.display()));
+    }
+    let mut progress = Progress::new(report);
+    if kind == Transfer::Copy {
+        for source in sources {
+            progress.add_total(measure(source));
+        }
+    }
+
+    let transferred = &mut outcome.transferred;
+    for source in sources {
+        let name = source
+            .file_name()
+            .ok_or_else(|| format!("Cannot transfer {}", source.display()))?;
+        ensure_not_into_itself(source, destination)?;
+        let mut target = destination.join(name);
+        let mut displaced = None;
+        if exists(&target) {
+            if same_entry(source, &target) {
+                // Pasting where the source already is.
+                match kind {
+                    Transfer::Move => {
+                        transferred.push(transferred_path(source, Some(&target), None));
+                        continue;
+                    }
+                    Transfer::Copy => target = free_name(&target),
+                }
+            } else {
+                match conflict {
+                    ConflictPolicy::Skip => {
+                        transferred.push(transferred_path(source, None, None));
+                        continue;
+                    }
+                    ConflictPolicy::Rename => target = free_name(&target),
+                    ConflictPolicy::Overwrite => {
+                        if source.starts_with(&target) {
+                            return Err(format!(
+                                "Cannot replace {} with an entry inside it",
+                                target.display()
+                            ));
+                        }
+                        displaced = Some(set_aside(&target)?);
+                    }
+                }
+            }
+        }
+
+        let result = match kind {
+            Transfer::Copy => copy_into_place(source, &target, &mut progress),
+            Transfer::Move => move_entry(source, &target, &mut progress),
+        };
+        // A target that is there was transferred whole, even if removing the
+        // source of a move failed afterwards.
+        if exists(&target) {
+            transferred.push(transferred_path(source, Some(&target), displaced));
+        } else if let Some(aside) = displaced {
+            let _ = fs::rename(&aside, &target);
+        }
+        result?;
+    }
+    progress.finish();
+    Ok(())
+}
+
+/// Copies `path` next to itself under the next free name and returns it.
+pub fn duplicate(path: &Path) -> Result<PathBuf, String> {
+    if !exists(path) {
+        return Err(format!("{} not found", path.display()));
+    }
+    let target = free_name(path);
+    copy_into_place(path, &target, &mut Progress::new(&mut |_| {}))?;
+    Ok(target)
+}
+
+/// Moves `source` to `target`, copying and deleting across devices.
+pub fn move_path(source: &Path, target: &Path) -> Result<(), String> {
+    ensure_not_into_itself(source, target.parent().unwrap_or(target))?;
+    move_entry(source, target, &mut Progress::new(&mut |_| {}))
+}
+
+/// Deletes a file, link or whole directory.
+pub fn remove_entry(path: &Path) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(path)
+        .map_err(|e| format!("Failed to stat {}: {e}", path.display()))?;
+    let result = if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.map_err(|e| format!("Failed to delete {}: {e}", path.display()))
+}
+
+/// The first of `name (1).ext`, `name (2).ext`, ... next to `target` that
+/// doesn't exist. A ` (n)` the name already carries is counted on from.
+pub fn free_name(target: &Path) -> PathBuf {
+    let parent = target.parent().unwrap_or(Path::new(""));
+    let file_name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (stem, extension) = if target.is_dir() {
+        (file_name.as_str(), "")
+    } else {
+        match file_name.rfind('.') {
+            // A leading dot starts a hidden name, not an extension.
+            Some(dot) if dot > 0 => file_name.split_at(dot),
+            _ => (file_name.as_str(), ""),
+        }
+    };
+    let (base, first) = stem
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once(" ("))
+        .and_then(|(base, n)| n.parse::<u32>().ok().map(|n| (base, n + 1)))
+        .unwrap_or((stem, 1));
+
+    (first..)
+        .map(|n| parent.join(format!("{base} ({n}){extension}")))
+        .find(|candidate| !exists(candidate))
+        .expect("some counter is free")
+}
+
+fn copy_entry(source: &Path, target: &Path, progress: &mut Progress) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(source)
+        .map_err(|e| format!("Failed to stat {}: {e}", source.display()))?;
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        copy_symlink(source, target)
+            .map_err(|e| format!("Failed to copy link {}: {e}", source.display()))?;
+        progress.file_done(source, 0);
+    } else if file_type.is_dir() {
+        fs::create_dir(target)
+            .map_err(|e| format!("Failed to create directory {}: {e}", target.display()))?;
+        let entries = fs::read_dir(source)
+            .map_err(|e| format!("Failed to read {}: {e}", source.display()))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Failed to read {}: {e}", source.display()))?;
+            copy_entry(&entry.path(), &target.join(entry.file_name()), progress)?;
+        }
+        let _ = fs::set_permissions(target, metadata.permissions());
+    } else {
+        let bytes = fs::copy(source, target)
+            .map_err(|e| format!("Failed to copy {}: {e}", source.display()))?;
+        progress.file_done(source, bytes);
+    }
+    Ok(())
+}
+
+/// Copies `source` to a temp sibling of `target` and renames it into place.
+/// A failed copy only removes the temp copy, never what `target` was.
+fn copy_into_place(source: &Path, target: &Path, progress: &mut Progress) -> Result<(), String> {
+    let temp = temp_path(parent_dir(target), target);
+    let result = copy_entry(source, &temp, progress).and_then(|()| {
+        fs::rename(&temp, target)
+            .map_err(|e| format!("Failed to move {} into place: {e}", target.display()))
+    });
+    if result.is_err() && exists(&temp) {
+        let _ = remove_entry(&temp);
+    }
+    result
+}
+
+/// Renames `target` to a temp sibling, to be removed once its replacement
+/// is in place or renamed back if that fails.
+fn set_aside(target: &Path) -> Result<PathBuf, String> {
+    let aside = temp_path(parent_dir(target), target);
+    fs::rename(target, &aside)
+        .map_err(|e| format!("Failed to replace {}: {e}", target.display()))?;
+    Ok(aside)
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+fn move_entry(source: &Path, target: &Path, progress: &mut Progress) -> Result<(), String> {
+    match fs::rename(source, target) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            progress.add_total(measure(source));
+            // On failure the source is untouched and the target as it was.
+            copy_into_place(source, target, progress)?;
+            remove_entry(source).map_err(|e| {
+                format!(
+                    "Copied {} to {}, but removing the original failed: {e}",
+                    source.display(),
+                    target.display()
+                )
+            })
+        }
+        Err(e) => Err(format!(
+            "Failed to move {} to {}: {e}",
+            source.display(),
+            target.display()
+        )),
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+#[cfg(windows)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    let link = fs::read_link(source)?;
+    if source.is_dir() {
+        std::os::windows::fs::symlink_dir(link, target)
+    } else {
+        std::os::windows::fs::symlink_file(link, target)
+    }
+}
+
+/// A directory can't be copied or moved into its own subtree.
+fn ensure_not_into_itself(source: &Path, destination: &Path) -> Result<(), String> {
+    let is_dir = fs::symlink_metadata(source).is_ok_and(|m| m.is_dir());
+    let (Ok(source), Ok(destination)) = (source.canonicalize(), destination.canonicalize()) else {
+        return Ok(());
+    };
+    if is_dir && destination.starts_with(&source) {
+        return Err(format!("Cannot transfer {} into itself", source.display()));
+    }
+    Ok(())
+}
+
+/// Number of files and their total size below `path`, links counted as
+/// empty files. Unreadable parts are left out.
+pub fn measure(path: &Path) -> (u64, u64) {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return (0, 0);
+    };
+    if !metadata.is_dir() {
+        return (
+            1,
+            if metadata.is_file() {
+                metadata.len()
+            } else {
+                0
+            },
+        );
+    }
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| measure(&entry.path()))
+        .fold((0, 0), |(files, bytes), (f, b)| (files + f, bytes + b))
+}
+
+pub fn same_entry(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+pub fn exists(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
+fn transferred_path(
+    source: &Path,
+    target: Option<&Path>,
+    replaced: Option<PathBuf>,
+) -> TransferredPath {
+    TransferredPath {
+        source: source.to_string_lossy().to_string(),
+        target: target.map(|t| t.to_string_lossy().to_string()),
+        replaced,
+    }
+}
+
+struct Progress<'a> {
+    report: &'a mut dyn FnMut(&TransferProgress),
+    state: TransferProgress,
+    last_report: Option<Instant>,
+}
+
+impl<'a> Progress<'a> {
+    fn new(report: &'a mut dyn FnMut(&TransferProgress)) -> Self {
+        Self {
+            report,
+            state: TransferProgress::default(),
+            last_report: None,
+        }
+    }
+
+    fn add_total(&mut self, (files, bytes): (u64, u64)) {
+        self.state.files_total += files;
+        self.state.bytes_total += bytes;
+    }
+
+    fn file_done(&mut self, path: &Path, bytes: u64) {
+        self.state.files_done += 1;
+        self.state.bytes_done += bytes;
+        self.state.current = path.to_string_lossy().to_string();
+        if self
+            .last_report
+            .is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL)
+        {
+            self.last_report = Some(Instant::now());
+            (self.report)(&self.state);
+        }
+    }
+
+    /// Reports the final state, unless nothing was copied.
+    fn finish(&mut self) {
+        if self.state.files_done > 0 {
+            (self.report)(&self.state);
+        }
+    }
+}
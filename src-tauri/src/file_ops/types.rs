@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// What to do when the target of a copy or move already exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Replace the existing entry.
+    Overwrite,
+    /// Leave the existing entry and don't transfer the source.
+    Skip,
+    /// Transfer under the next free name, `file (1).rs`.
+    #[default]
+    Rename,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransferRequest {
+    pub sources: Vec<String>,
+    /// Directory the sources are copied or moved into.
+    pub destination: String,
+    #[serde(default)]
+    pub conflict: ConflictPolicy,
+    /// Echoed in progress events so the frontend can tell operations apart.
+    pub operation_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferredPath {
+    pub source: String,
+    /// Where the source ended up; `None` when it was skipped.
+    pub target: Option<String>,
+    /// The entry `target` replaced, renamed to a temp sibling. The caller
+    /// removes it or keeps it for undo.
+    #[serde(skip)]
+    pub replaced: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TransferProgress {
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// The file copied last.
+    pub current: String,
+}
+
+/// Payload of `file-transfer-progress` events.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferProgressEvent {
+    pub operation_id: Option<String>,
+    #[serde(flatten)]
+    pub progress: TransferProgress,
+}
+
+/// Kind of a structural file operation in the undo journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Create,
+    Rename,
+    Move,
+    Copy,
+    Duplicate,
+    Delete,
+    Trash,
+}
+
+/// An operation that can be undone or redone, for menu labels.
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationInfo {
+    pub kind: OperationKind,
+    /// Paths the operation was about: created, deleted or moved-to paths.
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JournalStatus {
+    pub undo: Option<OperationInfo>,
+    pub redo: Option<OperationInfo>,
+}
@@ -0,0 +1,117 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Replaces the contents of `path` without ever leaving it truncated: the
+/// data goes to a temp file in the same directory, is fsynced and then
+/// renamed over the target. A symlink is written through, so the link
+/// itself stays; permissions and, where allowed, ownership of the previous
+/// file are carried over.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let target = resolve_symlink(path)?;
+    let previous = fs::metadata(&target).ok();
+    let dir = target
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let temp = temp_path(dir, &target);
+
+    let result = (|| {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        // Created with the previous mode, so the data is never readable more
+        // widely than the file it replaces.
+        create_mode(&mut options, previous.as_ref());
+        let mut file = options.open(&temp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        drop(file);
+
+        if let Some(previous) = &previous {
+            // chown clears setuid/setgid, so the mode is restored after it.
+            copy_ownership(&temp, previous);
+            fs::set_permissions(&temp, previous.permissions())?;
+        }
+        fs::rename(&temp, &target)?;
+        sync_dir(dir);
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Follows `path` through any chain of symlinks to the file they point to.
+/// Dangling links resolve to their (not yet existing) target.
+fn resolve_symlink(path: &Path) -> io::Result<PathBuf> {
+    let mut current = path.to_path_buf();
+    // Bounded like the OS does, so link cycles end in an error.
+    for _ in 0..40 {
+        match fs::symlink_metadata(&current) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                let link = fs::read_link(&current)?;
+                current = match current.parent() {
+                    Some(parent) => parent.join(link),
+                    None => link,
+                };
+            }
+            _ => return Ok(current),
+        }
+    }
+    Err(io::Error::other(format!(
+        "Too many levels of symbolic links: {}",
+        path.display()
+    )))
+}
+
+/// A fresh hidden name next to `target` in `dir`, on the same file system.
+pub fn temp_path(dir: &Path, target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let n = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    dir.join(format!(".{name}.{}-{n}.tmp", std::process::id()))
+}
+
+#[cfg(unix)]
+fn create_mode(options: &mut fs::OpenOptions, previous: Option<&fs::Metadata>) {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    if let Some(previous) = previous {
+        // Without the special bits: those only go back on after the chown.
+        options.mode(previous.permissions().mode() & 0o777);
+    }
+}
+
+#[cfg(not(unix))]
+fn create_mode(_options: &mut fs::OpenOptions, _previous: Option<&fs::Metadata>) {}
+
+#[cfg(unix)]
+fn copy_ownership(path: &Path, previous: &fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+    // Only succeeds when we may chown (own file, or running as root); a
+    // different owner is not worth failing the save over.
+    let _ = std::os::unix::fs::chown(path, Some(previous.uid()), Some(previous.gid()));
+}
+
+#[cfg(not(unix))]
+fn copy_ownership(_path: &Path, _previous: &fs::Metadata) {}
+
+/// Makes the rename durable. Directories can't be opened for syncing on
+/// Windows, where the rename is durable once it returns.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) {}
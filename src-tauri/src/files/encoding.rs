@@ -0,0 +1,134 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Number of leading bytes looked at by the heuristics.
+const SNIFF_LEN: usize = 64 * 1024;
+
+/// Text decoded from a file, with what is needed to write it back the same
+/// way.
+pub struct Decoded {
+    pub text: String,
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+    /// Some bytes were invalid in `encoding` and replaced by U+FFFD.
+    pub had_errors: bool,
+}
+
+/// Looks up an encoding by WHATWG label, e.g. `utf-8`, `windows-1251` or
+/// `utf-16le`.
+pub fn lookup(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| format!("Unknown encoding {label}"))
+}
+
+/// Detects the encoding of `bytes`: a BOM wins, then valid UTF-8, then
+/// UTF-16 recognised by its NUL byte pattern, then a statistical guess.
+pub fn detect(bytes: &[u8]) -> (&'static Encoding, bool) {
+    detect_prefix(bytes, true)
+}
+
+/// Like `detect`, for the first bytes of a file that may be read only in
+/// part. Unless `complete`, a UTF-8 sequence cut off at the end is fine.
+pub fn detect_prefix(bytes: &[u8], complete: bool) -> (&'static Encoding, bool) {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return (encoding, true);
+    }
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return (UTF_8, false),
+        Err(e) if !complete && e.error_len().is_none() => return (UTF_8, false),
+        Err(_) => {}
+    }
+    if let Some(encoding) = guess_utf16(sample) {
+        return (encoding, false);
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(sample, complete && sample.len() == bytes.len());
+    (detector.guess(None, true), false)
+}
+
+/// Whether the first bytes of a file look like text. NUL bytes mark binary
+/// files, except in UTF-16 where they are part of the text.
+pub fn looks_text(prefix: &[u8]) -> bool {
+    if Encoding::for_bom(prefix).is_some() || guess_utf16(prefix).is_some() {
+        return true;
+    }
+    !crate::search::walker::looks_binary(prefix)
+}
+
+/// Decodes `bytes`, in `forced` if given (reopen with encoding), otherwise
+/// in the detected encoding. A BOM matching the encoding is stripped.
+pub fn decode(bytes: &[u8], forced: Option<&'static Encoding>) -> Decoded {
+    let (encoding, _) = match forced {
+        Some(encoding) => (encoding, false),
+        None => detect(bytes),
+    };
+    let bom_len = match Encoding::for_bom(bytes) {
+        Some((found, len)) if found == encoding => len,
+        _ => 0,
+    };
+    let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+    Decoded {
+        text: text.into_owned(),
+        encoding,
+        bom: bom_len > 0,
+        had_errors,
+    }
+}
+
+/// Encodes `text` in `encoding`, with a leading BOM if `bom` is set. Fails
+/// when the text contains characters the encoding can't represent, instead
+/// of writing replacements.
+pub fn encode(text: &str, encoding: &'static Encoding, bom: bool) -> Result<Vec<u8>, String> {
+    // encoding_rs only decodes UTF-16, so it is written by hand.
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let le = encoding == UTF_16LE;
+        let mut bytes = Vec::with_capacity(text.len() * 2 + 2);
+        if bom {
+            bytes.extend_from_slice(if le { &[0xFF, 0xFE] } else { &[0xFE, 0xFF] });
+        }
+        for unit in text.encode_utf16() {
+            bytes.extend_from_slice(&if le {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            });
+        }
+        return Ok(bytes);
+    }
+
+    let (encoded, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        return Err(format!(
+            "The content contains characters that can't be saved as {}",
+            encoding.name()
+        ));
+    }
+    let mut bytes = Vec::with_capacity(encoded.len() + 3);
+    if bom && encoding == UTF_8 {
+        bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+/// UTF-16 without BOM: mostly ASCII text has a NUL in every other byte.
+fn guess_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let sample = &sample[..sample.len() & !1];
+    if sample.len() < 4 {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let even_nuls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nuls = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+    if odd_nuls * 10 >= pairs * 4 && even_nuls * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_nuls * 10 >= pairs * 4 && odd_nuls * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
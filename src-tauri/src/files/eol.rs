@@ -0,0 +1,68 @@
+use crate::files::types::{LineEnding, LineEndingPolicy, WriteFileRequest};
+
+/// Line ending facts about a text.
+pub struct LineEndings {
+    /// The line ending most lines use; `None` for text without line breaks.
+    pub dominant: Option<LineEnding>,
+    /// Both `\n` and `\r\n` occur.
+    pub mixed: bool,
+    pub final_newline: bool,
+}
+
+pub fn detect(text: &str) -> LineEndings {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    let dominant = match (lf, crlf) {
+        (0, 0) => None,
+        (lf, crlf) if crlf > lf => Some(LineEnding::Crlf),
+        _ => Some(LineEnding::Lf),
+    };
+    LineEndings {
+        dominant,
+        mixed: lf > 0 && crlf > 0,
+        final_newline: text.ends_with('\n'),
+    }
+}
+
+/// Applies the save policies of `request` to its content. `on_disk` describes
+/// the file being replaced: `keep` restores its dominant line ending when
+/// the editor handed over different ones. Where either side mixes line
+/// endings there is no single one to restore, so `keep` leaves every line
+/// its own and only the whitespace policies change the text.
+pub fn apply(request: &WriteFileRequest, on_disk: Option<&LineEndings>) -> String {
+    let content = detect(&request.content);
+    let target = match request.line_ending {
+        LineEndingPolicy::Lf => Some(LineEnding::Lf),
+        LineEndingPolicy::Crlf => Some(LineEnding::Crlf),
+        LineEndingPolicy::Keep if content.mixed || on_disk.is_some_and(|d| d.mixed) => None,
+        LineEndingPolicy::Keep => Some(
+            on_disk
+                .and_then(|d| d.dominant)
+                .or(content.dominant)
+                .unwrap_or(LineEnding::Lf),
+        ),
+    };
+
+    let mut text = String::with_capacity(request.content.len());
+    for segment in request.content.split_inclusive('\n') {
+        let (mut line, ending) = match segment.strip_suffix('\n') {
+            Some(line) => match line.strip_suffix('\r') {
+                Some(line) => (line, LineEnding::Crlf),
+                None => (line, LineEnding::Lf),
+            },
+            None => (segment, LineEnding::Lf),
+        };
+        if request.trim_trailing_whitespace {
+            line = line.trim_end_matches([' ', '\t']);
+        }
+        text.push_str(line);
+        if segment.ends_with('\n') {
+            text.push_str(target.unwrap_or(ending).as_str());
+        }
+    }
+    if request.insert_final_newline && !text.is_empty() && !text.ends_with('\n') {
+        let separator = target.or(content.dominant).unwrap_or(LineEnding::Lf);
+        text.push_str(separator.as_str());
+    }
+    text
+}
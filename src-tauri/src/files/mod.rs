@@ -0,0 +1,109 @@
+use std::{fs, path::Path};
+
+use encoding_rs::UTF_8;
+
+pub mod atomic;
+pub mod encoding;
+pub mod eol;
+pub mod stream;
+pub mod types;
+pub mod version;
+pub mod viewer;
+
+use types::{FileVersion, TextFileContent, WriteFileError, WriteFileRequest};
+
+/// Reads and decodes a text file, detecting its encoding unless `forced`
+/// names one.
+pub fn read_text(path: &Path, forced: Option<&str>) -> Result<TextFileContent, String> {
+    if !path.is_file() {
+        return Err(format!("File {} not found", path.display()));
+    }
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size > viewer::LARGE_FILE_THRESHOLD {
+        // The UI opens such files in the chunked viewer instead (see `stat`).
+        return Err(format!(
+            "File {} is too large to open in the editor ({size} bytes)",
+            path.display()
+        ));
+    }
+    let forced = forced.map(encoding::lookup).transpose()?;
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let version = version::version_of(&bytes, fs::metadata(path).ok().as_ref());
+    let decoded = encoding::decode(&bytes, forced);
+    let line_endings = eol::detect(&decoded.text);
+    Ok(TextFileContent {
+        content: decoded.text,
+        encoding: decoded.encoding.name().to_string(),
+        bom: decoded.bom,
+        had_errors: decoded.had_errors,
+        eol: line_endings.dominant,
+        mixed_eol: line_endings.mixed,
+        final_newline: line_endings.final_newline,
+        version,
+    })
+}
+
+/// Outcome of `write_text`.
+pub struct Written {
+    pub version: FileVersion,
+    /// Raw contents the write replaced, if the file existed.
+    pub previous: Option<Vec<u8>>,
+    /// Raw contents written.
+    pub bytes: Vec<u8>,
+}
+
+/// Applies the save policies to `request.content`, encodes and writes it.
+/// Unless the request says otherwise, the encoding, BOM and line endings of
+/// the existing file are kept.
+pub fn write_text(path: &Path, request: &WriteFileRequest) -> Result<Written, WriteFileError> {
+    let on_disk = fs::read(path).ok();
+    if let Some(expected) = &request.expected_version {
+        let actual = on_disk
+            .as_deref()
+            .map(|bytes| version::version_of(bytes, fs::metadata(path).ok().as_ref()));
+        if !actual
+            .as_ref()
+            .is_some_and(|actual| version::matches(expected, actual))
+        {
+            return Err(WriteFileError::Conflict {
+                path: path.to_string_lossy().replace('\\', "/"),
+                expected: expected.clone(),
+                actual,
+            });
+        }
+    }
+
+    let existing = on_disk
+        .as_deref()
+        .map(|bytes| encoding::decode(bytes, None));
+    let (on_disk_encoding, on_disk_bom) = existing
+        .as_ref()
+        .map_or((UTF_8, false), |d| (d.encoding, d.bom));
+    let on_disk_eol = existing.map(|d| eol::detect(&d.text));
+
+    let target = match &request.encoding {
+        Some(label) => encoding::lookup(label)?,
+        None => on_disk_encoding,
+    };
+    let bom = request
+        .bom
+        .unwrap_or(on_disk_bom && target == on_disk_encoding);
+    let text = eol::apply(request, on_disk_eol.as_ref());
+    let bytes = encoding::encode(&text, target, bom)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            format!(
+                "Failed to create parent directory {}: {e}",
+                parent.display()
+            )
+        })?;
+    }
+    atomic::write_atomic(path, &bytes)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    Ok(Written {
+        version: version::version_of(&bytes, fs::metadata(path).ok().as_ref()),
+        previous: on_disk,
+        bytes,
+    })
+}
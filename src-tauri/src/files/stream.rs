@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use tauri::{AppHandle, Emitter};
+
+use crate::files::{
+    types::{FileStreamChunkEvent, FileStreamEndEvent},
+    viewer,
+};
+
+/// Chunks a stream may have in flight before the viewer acknowledges one.
+pub const STREAM_WINDOW: usize = 4;
+/// A viewer that neither acknowledges nor cancels for this long is taken to
+/// be gone, and its stream ends.
+const ACK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Read-only viewer streams: a file is read front to back in chunks that
+/// are emitted as `file-stream-chunk` events, ending with one
+/// `file-stream-end` event. At most `STREAM_WINDOW` chunks are emitted
+/// ahead of the viewer's `ack`s, so a slow webview isn't flooded.
+#[derive(Clone, Default)]
+pub struct FileStreamState {
+    active: Arc<Mutex<HashMap<u32, Arc<Flow>>>>,
+    next_id: Arc<AtomicU32>,
+}
+
+struct Flow {
+    state: Mutex<FlowState>,
+    wake: Condvar,
+}
+
+struct FlowState {
+    /// Chunks that may still be emitted without an ack.
+    credit: usize,
+    cancelled: bool,
+}
+
+impl Flow {
+    fn new(credit: usize) -> Self {
+        Self {
+            state: Mutex::new(FlowState {
+                credit,
+                cancelled: false,
+            }),
+            wake: Condvar::new(),
+        }
+    }
+
+    /// Waits for credit to emit a chunk. Returns `Err` with a message when
+    /// the viewer stopped acknowledging, `Ok(false)` when cancelled.
+    fn acquire(&self) -> Result<bool, String> {
+        let state = self.state.lock().expect("file stream poisoned");
+        let (mut state, timeout) = self
+            .wake
+            .wait_timeout_while(state, ACK_TIMEOUT, |s| s.credit == 0 && !s.cancelled)
+            .expect("file stream poisoned");
+        if state.cancelled {
+            return Ok(false);
+        }
+        if timeout.timed_out() {
+            return Err(format!(
+                "No chunk was acknowledged for {} s",
+                ACK_TIMEOUT.as_secs()
+            ));
+        }
+        state.credit -= 1;
+        Ok(true)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut FlowState)) {
+        change(&mut self.state.lock().expect("file stream poisoned"));
+        self.wake.notify_all();
+    }
+
+    fn cancelled(&self) -> bool {
+        self.state.lock().expect("file stream poisoned").cancelled
+    }
+}
+
+impl FileStreamState {
+    pub fn start(
+        &self,
+        app: &AppHandle,
+        path: PathBuf,
+        encoding: Option<String>,
+        chunk_size: usize,
+    ) -> Result<u32, String> {
+        let mut file =
+            fs::File::open(&path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+        let encoding = viewer::chunk_encoding(&mut file, encoding.as_deref())?;
+
+        let stream_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let flow = Arc::new(Flow::new(STREAM_WINDOW));
+        self.active
+            .lock()
+            .expect("file streams poisoned")
+            .insert(stream_id, Arc::clone(&flow));
+
+        let state = self.clone();
+        let app = app.clone();
+        thread::spawn(move || {
+            let mut offset = Some(0);
+            let mut error = None;
+            while let Some(current) = offset {
+                match flow.acquire() {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                }
+                match viewer::read_text_chunk(&mut file, encoding, current, chunk_size) {
+                    Ok(chunk) => {
+                        offset = chunk.next_offset;
+                        let _ = app.emit(
+                            "file-stream-chunk",
+                            FileStreamChunkEvent { stream_id, chunk },
+                        );
+                    }
+                    Err(e) => {
+                        error = Some(e);
+                        break;
+                    }
+                }
+            }
+            let _ = app.emit(
+                "file-stream-end",
+                FileStreamEndEvent {
+                    stream_id,
+                    cancelled: flow.cancelled(),
+                    error,
+                },
+            );
+            state
+                .active
+                .lock()
+                .expect("file streams poisoned")
+                .remove(&stream_id);
+        });
+
+        Ok(stream_id)
+    }
+
+    /// The viewer is done with a chunk; lets the stream emit another one.
+    pub fn ack(&self, stream_id: u32) {
+        if let Some(flow) = self.flow(stream_id) {
+            flow.update(|s| s.credit += 1);
+        }
+    }
+
+    pub fn cancel(&self, stream_id: u32) {
+        if let Some(flow) = self.flow(stream_id) {
+            flow.update(|s| s.cancelled = true);
+        }
+    }
+
+    pub fn cancel_all(&self) {
+        for flow in self.active.lock().expect("file streams poisoned").values() {
+            flow.update(|s| s.cancelled = true);
+        }
+    }
+
+    fn flow(&self, stream_id: u32) -> Option<Arc<Flow>> {
+        self.active
+            .lock()
+            .expect("file streams poisoned")
+            .get(&stream_id)
+            .cloned()
+    }
+}
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// Line endings written on save.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndingPolicy {
+    /// Whatever the file on disk uses; for new files, what the content uses.
+    #[default]
+    Keep,
+    Lf,
+    Crlf,
+}
+
+/// Identifies the contents of a file on disk, to detect external changes
+/// between loading and saving it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileVersion {
+    /// Modification time in milliseconds since the Unix epoch.
+    pub mtime: Option<i64>,
+    pub size: u64,
+    /// SHA-256 of the raw bytes, hex encoded.
+    pub hash: String,
+}
+
+/// A text file decoded for the editor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextFileContent {
+    pub content: String,
+    /// WHATWG name of the encoding, e.g. `UTF-8` or `windows-1251`.
+    pub encoding: String,
+    pub bom: bool,
+    /// Invalid byte sequences were replaced by U+FFFD, so saving would not
+    /// round-trip. Usually means the wrong encoding was picked.
+    pub had_errors: bool,
+    /// Dominant line ending; `None` when the file has no line breaks.
+    pub eol: Option<LineEnding>,
+    /// Both LF and CRLF occur in the file.
+    pub mixed_eol: bool,
+    pub final_newline: bool,
+    /// To be passed back as `expected_version` when saving.
+    pub version: FileVersion,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WriteFileRequest {
+    pub path: String,
+    pub content: String,
+    /// Encoding to save with. Defaults to the encoding the file has on disk,
+    /// or UTF-8 for new files.
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// Whether to write a BOM. Defaults to keeping the file's current BOM.
+    #[serde(default)]
+    pub bom: Option<bool>,
+    #[serde(default)]
+    pub line_ending: LineEndingPolicy,
+    /// Strips spaces and tabs at the end of every line.
+    #[serde(default)]
+    pub trim_trailing_whitespace: bool,
+    /// Ends non-empty content with a line break.
+    #[serde(default)]
+    pub insert_final_newline: bool,
+    /// Version the editor loaded. When set, the save is rejected with
+    /// `WriteFileError::Conflict` if the file on disk is a different version.
+    #[serde(default)]
+    pub expected_version: Option<FileVersion>,
+}
+
+/// Error of `write_file`, tagged by `kind` so the UI can tell a conflict,
+/// which it turns into a diff or merge prompt, from other failures.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WriteFileError {
+    /// The file changed on disk since `expected` was read. `actual` is
+    /// `None` when it was deleted.
+    Conflict {
+        path: String,
+        expected: FileVersion,
+        actual: Option<FileVersion>,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for WriteFileError {
+    fn from(message: String) -> Self {
+        WriteFileError::Failed { message }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Text,
+    Binary,
+}
+
+/// How the UI should open a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenMode {
+    /// Load it whole into the editor.
+    Editor,
+    /// Too large for the editor: show it read-only, read in chunks.
+    Viewer,
+    Hex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub path: String,
+    pub size: u64,
+    /// Modification time in milliseconds since the Unix epoch.
+    pub modified: Option<i64>,
+    pub kind: FileKind,
+    /// Detected from the first bytes; `None` for binary files.
+    pub encoding: Option<String>,
+    pub mode: OpenMode,
+}
+
+/// A decoded range of a text file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextChunk {
+    /// Byte offset the chunk starts at.
+    pub offset: u64,
+    /// Byte offset of the following chunk; `None` at the end of the file.
+    pub next_offset: Option<u64>,
+    /// Size of the whole file in bytes.
+    pub size: u64,
+    pub data: String,
+    pub encoding: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HexRow {
+    pub offset: u64,
+    /// Space-separated lowercase hex bytes.
+    pub hex: String,
+    /// Printable ASCII, other bytes shown as `.`.
+    pub ascii: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HexDump {
+    pub offset: u64,
+    pub size: u64,
+    pub rows: Vec<HexRow>,
+    pub next_offset: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStreamChunkEvent {
+    pub stream_id: u32,
+    pub chunk: TextChunk,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStreamEndEvent {
+    pub stream_id: u32,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
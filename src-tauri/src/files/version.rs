@@ -0,0 +1,30 @@
+use std::{fs, time::UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+
+use crate::files::types::FileVersion;
+
+/// Hex SHA-256 of `bytes`. Versions, replace previews, local history blobs
+/// and hot exit backups all identify contents by it.
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Version of file contents read from disk together with its metadata.
+pub fn version_of(bytes: &[u8], metadata: Option<&fs::Metadata>) -> FileVersion {
+    FileVersion {
+        mtime: metadata
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64),
+        size: bytes.len() as u64,
+        hash: content_hash(bytes),
+    }
+}
+
+/// Whether the file still is the version the editor loaded. The hash
+/// decides: a touched but unchanged file (e.g. a formatter that found
+/// nothing to do) is no conflict.
+pub fn matches(expected: &FileVersion, actual: &FileVersion) -> bool {
+    expected.size == actual.size && expected.hash == actual.hash
+}
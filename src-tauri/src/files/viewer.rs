@@ -0,0 +1,216 @@
+use std::{
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+use crate::{
+    files::{
+        encoding,
+        types::{FileInfo, FileKind, HexDump, HexRow, OpenMode, TextChunk},
+    },
+    search::walker::BINARY_SNIFF_LEN,
+};
+
+/// Files above this size are not loaded into the editor as a whole; they
+/// open in the read-only viewer, which reads them in chunks.
+pub const LARGE_FILE_THRESHOLD: u64 = 32 * 1024 * 1024;
+pub const DEFAULT_CHUNK_SIZE: usize = 256 * 1024;
+const MAX_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+const DEFAULT_HEX_LENGTH: usize = 4 * 1024;
+const MAX_HEX_LENGTH: usize = 64 * 1024;
+const HEX_ROW_LEN: usize = 16;
+
+/// Size and classification of a file, read from its first bytes only, so
+/// the UI can pick how to open it.
+pub fn stat(path: &Path) -> Result<FileInfo, String> {
+    let metadata =
+        fs::metadata(path).map_err(|e| format!("Failed to stat {}: {e}", path.display()))?;
+    if !metadata.is_file() {
+        return Err(format!("File {} not found", path.display()));
+    }
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let prefix = read_at(&mut file, 0, BINARY_SNIFF_LEN)?;
+    let size = metadata.len();
+
+    let (kind, encoding) = if encoding::looks_text(&prefix) {
+        let complete = prefix.len() as u64 >= size;
+        let (encoding, _) = encoding::detect_prefix(&prefix, complete);
+        (FileKind::Text, Some(encoding.name().to_string()))
+    } else {
+        (FileKind::Binary, None)
+    };
+    let mode = match kind {
+        FileKind::Binary => OpenMode::Hex,
+        FileKind::Text if size > LARGE_FILE_THRESHOLD => OpenMode::Viewer,
+        FileKind::Text => OpenMode::Editor,
+    };
+
+    Ok(FileInfo {
+        path: path.to_string_lossy().replace('\\', "/"),
+        size,
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64),
+        kind,
+        encoding,
+        mode,
+    })
+}
+
+/// Encoding used for ranged reads: `forced`, or detected from the head of
+/// the file.
+pub fn chunk_encoding(
+    file: &mut fs::File,
+    forced: Option<&str>,
+) -> Result<&'static Encoding, String> {
+    if let Some(label) = forced {
+        return encoding::lookup(label);
+    }
+    let head = read_at(file, 0, BINARY_SNIFF_LEN)?;
+    let complete = file.metadata().is_ok_and(|m| head.len() as u64 >= m.len());
+    Ok(encoding::detect_prefix(&head, complete).0)
+}
+
+/// Reads up to `length` bytes of text starting at `offset`. The chunk is cut
+/// back to a character boundary, so `next_offset` is where the next chunk
+/// starts without splitting a character. Legacy multi-byte encodings (e.g.
+/// Shift_JIS) are not boundary-aware and may split one.
+pub fn read_text_chunk(
+    file: &mut fs::File,
+    encoding: &'static Encoding,
+    offset: u64,
+    length: usize,
+) -> Result<TextChunk, String> {
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    let length = length.clamp(4, MAX_CHUNK_SIZE);
+    let bytes = read_at(file, offset, length)?;
+    let eof = offset + bytes.len() as u64 >= size;
+
+    let mut start = 0;
+    if offset == 0 {
+        if let Some((found, bom_len)) = Encoding::for_bom(&bytes) {
+            if found == encoding {
+                start = bom_len;
+            }
+        }
+    } else if encoding == UTF_8 {
+        // An offset inside a character: skip to the next one.
+        start = bytes
+            .iter()
+            .take(3)
+            .take_while(|&&b| b & 0xC0 == 0x80)
+            .count();
+    }
+    let end = if eof {
+        bytes.len()
+    } else {
+        complete_len(encoding, &bytes).max(start)
+    };
+    let (text, _) = encoding.decode_without_bom_handling(&bytes[start..end]);
+    let next = offset + end as u64;
+
+    Ok(TextChunk {
+        offset,
+        next_offset: (next < size).then_some(next),
+        size,
+        data: text.into_owned(),
+        encoding: encoding.name().to_string(),
+    })
+}
+
+/// A hex view of `length` bytes from `offset`, 16 bytes per row.
+pub fn hex_dump(path: &Path, offset: u64, length: Option<usize>) -> Result<HexDump, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    let length = length.unwrap_or(DEFAULT_HEX_LENGTH).min(MAX_HEX_LENGTH);
+    let bytes = read_at(&mut file, offset, length)?;
+
+    let rows = bytes
+        .chunks(HEX_ROW_LEN)
+        .enumerate()
+        .map(|(idx, row)| HexRow {
+            offset: offset + (idx * HEX_ROW_LEN) as u64,
+            hex: row
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            ascii: row
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+    let next = offset + bytes.len() as u64;
+
+    Ok(HexDump {
+        offset,
+        size,
+        rows,
+        next_offset: (next < size).then_some(next),
+    })
+}
+
+fn read_at(file: &mut fs::File, offset: u64, length: usize) -> Result<Vec<u8>, String> {
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek: {e}"))?;
+    let mut bytes = Vec::with_capacity(length);
+    file.take(length as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read: {e}"))?;
+    Ok(bytes)
+}
+
+/// Length of `bytes` without a character cut off at the end.
+fn complete_len(encoding: &'static Encoding, bytes: &[u8]) -> usize {
+    let len = bytes.len();
+    if encoding == UTF_8 {
+        // Find the last lead byte and check its sequence is complete.
+        for idx in (len.saturating_sub(3)..len).rev() {
+            let b = bytes[idx];
+            if b & 0xC0 == 0x80 {
+                continue;
+            }
+            let needed = match b {
+                0xF0..=0xFF => 4,
+                0xE0..=0xEF => 3,
+                0xC0..=0xDF => 2,
+                _ => 1,
+            };
+            return if len - idx < needed { idx } else { len };
+        }
+        len
+    } else if encoding == UTF_16LE || encoding == UTF_16BE {
+        let even = len & !1;
+        if even < 2 {
+            return even;
+        }
+        let last = [bytes[even - 2], bytes[even - 1]];
+        let unit = if encoding == UTF_16LE {
+            u16::from_le_bytes(last)
+        } else {
+            u16::from_be_bytes(last)
+        };
+        // A high surrogate needs the low one from the next chunk.
+        if (0xD800..=0xDBFF).contains(&unit) {
+            even - 2
+        } else {
+            even
+        }
+    } else {
+        len
+    }
+}
@@ -69,7 +69,7 @@ fn decode_bytes(bytes: &[u8]) -> (String, bool) {
     }
 }
 
-fn language_from_path(path: &Path) -> String {
+pub fn language_from_path(path: &Path) -> String {
     path.extension()
         .and_then(|ext| ext.to_str())
         .map(|ext| {
@@ -1,13 +1,13 @@
 use std::{
     path::PathBuf,
-    sync::{mpsc, Arc, Mutex},
-    thread,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
-use notify::{event::Event, recommended_watcher, RecommendedWatcher, RecursiveMode, Watcher};
 use tauri::{AppHandle, Emitter};
 
+use crate::watcher::types::FileChangeBatch;
+
 pub mod diff;
 pub mod history;
 pub mod ops;
@@ -26,7 +26,6 @@ pub enum GitError {
     Git(String),
     Io(String),
     InvalidInput(String),
-    Notify(String),
 }
 
 impl From<git2::Error> for GitError {
@@ -41,19 +40,11 @@ impl From<std::io::Error> for GitError {
     }
 }
 
-impl From<notify::Error> for GitError {
-    fn from(value: notify::Error) -> Self {
-        GitError::Notify(value.to_string())
-    }
-}
-
 #[derive(Clone, Default)]
 pub struct GitState {
     repository_root: Arc<Mutex<Option<PathBuf>>>,
     status_cache: Arc<Mutex<Option<types::GitRepositoryStatus>>>,
     cache_timestamp: Arc<Mutex<Option<Instant>>>,
-    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
-    watched_root: Arc<Mutex<Option<PathBuf>>>,
     last_emit: Arc<Mutex<Option<Instant>>>,
 }
 
@@ -94,44 +85,21 @@ impl GitState {
         *self.cache_timestamp.lock().expect("cache ts poisoned") = None;
     }
 
-    pub fn ensure_watcher(&self, app: &AppHandle) -> GitResult<()> {
-        let repo_root = match self.repository_root() {
-            Some(root) => root,
-            None => {
-                self.drop_watcher();
-                return Ok(());
-            }
+    /// Subscriber of the shared file watcher: any change in the work tree or
+    /// in the git metadata makes the cached status stale.
+    pub fn apply_changes(&self, app: &AppHandle, batch: &FileChangeBatch) {
+        let Some(repo_root) = self.repository_root() else {
+            return;
         };
-
-        {
-            let watched = self.watched_root.lock().expect("watched_root poisoned");
-            if watched.as_ref() == Some(&repo_root) {
-                return Ok(());
-            }
+        let touches_repo = batch.git_metadata
+            || batch
+                .paths()
+                .iter()
+                .any(|path| path.starts_with(&repo_root));
+        if touches_repo {
+            self.invalidate_status_cache();
+            self.emit_status_changed(app);
         }
-
-        self.drop_watcher();
-
-        let (tx, rx) = mpsc::channel::<Result<Event, notify::Error>>();
-        let mut watcher = recommended_watcher(move |res| {
-            let _ = tx.send(res);
-        })?;
-        watcher.watch(&repo_root, RecursiveMode::Recursive)?;
-
-        let handle = app.clone();
-        let state_for_thread = self.clone();
-        thread::spawn(move || {
-            while let Ok(event_result) = rx.recv() {
-                if event_result.is_ok() {
-                    state_for_thread.invalidate_status_cache();
-                    state_for_thread.emit_status_changed(&handle);
-                }
-            }
-        });
-
-        *self.watched_root.lock().expect("watched_root poisoned") = Some(repo_root);
-        *self.watcher.lock().expect("watcher poisoned") = Some(watcher);
-        Ok(())
     }
 
     pub fn emit_status_changed(&self, app: &AppHandle) {
@@ -145,11 +113,6 @@ impl GitState {
         *last_emit = Some(now);
         let _ = app.emit("git-status-changed", ());
     }
-
-    fn drop_watcher(&self) {
-        *self.watcher.lock().expect("watcher poisoned") = None;
-        *self.watched_root.lock().expect("watched_root poisoned") = None;
-    }
 }
 
 pub use diff::{staged_diff, working_diff};
@@ -0,0 +1,119 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub mod types;
+
+use crate::files::{
+    atomic::write_atomic,
+    version::{self, content_hash},
+};
+use types::{BufferBackup, BufferBackupRequest, BufferKey, RestoredBuffer};
+
+/// Backups of dirty editor buffers for hot exit: the frontend sends the
+/// contents of dirty buffers periodically, and they are offered again on
+/// the next launch until the buffer is saved or discarded. Each buffer is
+/// one JSON file named after the hash of its key.
+#[derive(Clone, Default)]
+pub struct HotExitState {
+    dir: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl HotExitState {
+    pub fn open(&self, dir: PathBuf) -> Result<(), String> {
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        *self.dir.lock().expect("hot exit dir poisoned") = Some(dir);
+        Ok(())
+    }
+
+    fn with_dir<T>(&self, f: impl FnOnce(&Path) -> Result<T, String>) -> Result<T, String> {
+        let guard = self.dir.lock().expect("hot exit dir poisoned");
+        let dir = guard
+            .as_deref()
+            .ok_or_else(|| "Buffer backups are not available".to_string())?;
+        f(dir)
+    }
+
+    pub fn backup(&self, request: BufferBackupRequest) -> Result<(), String> {
+        let backup = BufferBackup {
+            key: request.key,
+            content: request.content,
+            language: request.language,
+            version: request.version,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0),
+        };
+        let json = serde_json::to_vec(&backup)
+            .map_err(|e| format!("Failed to serialize buffer backup: {e}"))?;
+        self.with_dir(|dir| {
+            write_atomic(&backup_file(dir, &backup.key), &json)
+                .map_err(|e| format!("Failed to write buffer backup: {e}"))
+        })
+    }
+
+    /// Forgets the backup of a buffer that was saved or discarded. A missing
+    /// backup is fine: most saves happen before the next backup round.
+    pub fn discard(&self, key: &BufferKey) -> Result<(), String> {
+        self.with_dir(|dir| match fs::remove_file(backup_file(dir, key)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove buffer backup: {e}"))
+            }
+            _ => Ok(()),
+        })
+    }
+
+    /// All backups left by the previous session, oldest first. They stay on
+    /// disk until discarded, so a crash during restore loses nothing.
+    /// Unreadable backup files are skipped.
+    pub fn restore(&self) -> Result<Vec<RestoredBuffer>, String> {
+        let mut backups: Vec<BufferBackup> = self.with_dir(|dir| {
+            let entries =
+                fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+            Ok(entries
+                .flatten()
+                .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+                .filter_map(|entry| fs::read(entry.path()).ok())
+                .filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+                .collect())
+        })?;
+        backups.sort_by_key(|backup| backup.timestamp);
+
+        Ok(backups
+            .into_iter()
+            .map(|backup| RestoredBuffer {
+                disk_changed: disk_changed(&backup),
+                backup,
+            })
+            .collect())
+    }
+}
+
+fn backup_file(dir: &Path, key: &BufferKey) -> PathBuf {
+    let name = match key {
+        BufferKey::File { path } => format!("file:{path}"),
+        BufferKey::Untitled { id } => format!("untitled:{id}"),
+    };
+    dir.join(format!("{}.json", content_hash(name.as_bytes())))
+}
+
+fn disk_changed(backup: &BufferBackup) -> bool {
+    let BufferKey::File { path } = &backup.key else {
+        return false;
+    };
+    let Some(expected) = &backup.version else {
+        return false;
+    };
+    let path = Path::new(path);
+    match fs::read(path) {
+        Ok(bytes) => {
+            let actual = version::version_of(&bytes, fs::metadata(path).ok().as_ref());
+            !version::matches(expected, &actual)
+        }
+        Err(_) => true,
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::files::types::FileVersion;
+
+/// What identifies a dirty buffer across restarts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum BufferKey {
+    /// A buffer of a file on disk, by absolute path.
+    File { path: String },
+    /// A buffer never saved, by the id the frontend gave it.
+    Untitled { id: String },
+}
+
+/// Unsaved contents of a buffer, as sent by the frontend.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BufferBackupRequest {
+    pub key: BufferKey,
+    pub content: String,
+    pub language: Option<String>,
+    /// Version of the file the buffer was loaded from, if any.
+    pub version: Option<FileVersion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferBackup {
+    pub key: BufferKey,
+    pub content: String,
+    pub language: Option<String>,
+    pub version: Option<FileVersion>,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: i64,
+}
+
+/// A backup found on launch.
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoredBuffer {
+    #[serde(flatten)]
+    pub backup: BufferBackup,
+    /// The file on disk is no longer the version the buffer was edited
+    /// from (or is gone), so restoring it will overwrite outside changes.
+    pub disk_changed: bool,
+}
@@ -22,41 +22,90 @@
 // - serde / serde_json
 // -----------------------------------------------------------------------------
 
+mod file_ops;
+mod files;
 mod git;
+mod hot_exit;
+mod local_history;
+mod lsp;
+mod quick_open;
+mod search;
+mod symbols;
+mod terminal;
+mod tree;
+mod watcher;
+mod workspace;
 
-use notify::{
-    event::{Event, EventKind, ModifyKind},
-    recommended_watcher, RecommendedWatcher, RecursiveMode, Watcher,
-};
-use once_cell::sync::Lazy;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
     fs,
-    io::{BufRead, BufReader, Write},
     path::{Path, PathBuf},
     process::Command,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        mpsc, Arc, Mutex,
-    },
+    sync::Arc,
     thread,
-    time::UNIX_EPOCH,
 };
-use tauri::{AppHandle, Emitter, Manager, State};
-
+use tauri::{AppHandle, Emitter, Manager, RunEvent, State};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use file_ops::{
+    journal::{Change, FileJournalState},
+    types::{
+        JournalStatus, OperationInfo, OperationKind, TransferProgressEvent, TransferRequest,
+        TransferredPath,
+    },
+    Transfer,
+};
+use files::{
+    stream::FileStreamState,
+    types::{
+        FileInfo, FileVersion, HexDump, TextChunk, TextFileContent, WriteFileError,
+        WriteFileRequest,
+    },
+};
 use git::{
     types::{CommitInfo, GitDiff, GitFileStatus, GitRepositoryStatus},
     GitState,
 };
+use hot_exit::{
+    types::{BufferBackupRequest, BufferKey, RestoredBuffer},
+    HotExitState,
+};
+use local_history::{
+    types::{HistoryDiff, HistoryEntry},
+    LocalHistoryState,
+};
+use lsp::{
+    types::{LspServerInfo, StartLspRequest},
+    LspState,
+};
+use quick_open::{
+    types::{QuickOpenIndexInfo, QuickOpenMatch, QuickOpenRequest},
+    QuickOpenState,
+};
+use search::{
+    types::{ApplyReplaceRequest, ReplaceSummary, SearchFilesRequest},
+    SearchContext, SearchState,
+};
+use symbols::{
+    types::{SymbolIndexStatus, SymbolMatch, SymbolQueryRequest},
+    SymbolState,
+};
+use terminal::{
+    types::{CreateTerminalRequest, TerminalInfo},
+    TerminalState,
+};
+use tree::types::{DirectoryPage, FileEntry, ReadDirectoryRequest};
+use watcher::{
+    types::{FileChangeBatch, WatcherConfig, WatcherStatus},
+    WatcherState,
+};
+use workspace::{policy::Access, types::WorkspaceRoot, WorkspaceState};
 
 #[derive(Debug)]
 struct AppPaths {
     config_dir: PathBuf,
 }
 
-static FILE_WATCHER: Lazy<Mutex<Option<RecommendedWatcher>>> = Lazy::new(|| Mutex::new(None));
-
 impl AppPaths {
     fn new(handle: &tauri::AppHandle) -> Result<Self, String> {
         let base = handle
@@ -119,29 +168,6 @@ pub struct AppliedChangeLike {
     pub value: serde_json::Value,
 }
 
-/// Search result hit for project-wide search
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchHit {
-    pub file: String,
-    pub line: u32,
-    pub column: u32,
-    pub match_text: String,
-    pub line_text: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileEntry {
-    pub id: String,
-    pub name: String,
-    pub path: String,
-    #[serde(rename = "type")]
-    pub node_type: String,
-    pub size: Option<u64>,
-    pub modified: Option<i64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub children: Option<Vec<FileEntry>>,
-}
-
 // -----------------------------------------------------------------------------
 // Low-level file helpers
 // -----------------------------------------------------------------------------
@@ -167,97 +193,16 @@ fn write_json_file<T: Serialize>(path: &PathBuf, value: &T) -> Result<(), String
         fs::create_dir_all(parent)
             .map_err(|e| format!("Failed to create dir {}: {e}", parent.display()))?;
     }
-    let mut file =
-        fs::File::create(path).map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
-    file.write_all(data.as_bytes())
-        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
-    Ok(())
-}
-
-const MAX_TREE_DEPTH: usize = 4;
-
-fn resolve_path(path_str: &str) -> Result<PathBuf, String> {
-    let candidate = PathBuf::from(path_str);
-    if candidate.is_absolute() {
-        return Ok(candidate);
-    }
-    std::env::current_dir()
-        .map_err(|e| format!("Failed to resolve current_dir: {e}"))
-        .map(|cwd| cwd.join(candidate))
-}
-
-fn metadata_timestamp(metadata: &fs::Metadata) -> Option<i64> {
-    metadata
-        .modified()
-        .ok()
-        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-        .map(|duration| duration.as_secs() as i64)
-}
-
-fn build_file_entry(path: &Path, root: &Path, depth: usize) -> Result<FileEntry, String> {
-    let metadata =
-        fs::metadata(path).map_err(|e| format!("Failed to stat {}: {e}", path.display()))?;
-    let relative = path.strip_prefix(root).unwrap_or(path);
-    let id = if relative.as_os_str().is_empty() {
-        ".".to_string()
-    } else {
-        relative.to_string_lossy().replace('\\', "/")
-    };
-    let name = path
-        .file_name()
-        .map(|name| name.to_string_lossy().into_owned())
-        .unwrap_or_else(|| relative.display().to_string());
-    let node_type = if metadata.is_dir() { "dir" } else { "file" }.to_string();
-    let children = if metadata.is_dir() && depth > 0 {
-        Some(read_workspace_entries(path, root, depth - 1)?)
-    } else {
-        None
-    };
-    Ok(FileEntry {
-        id,
-        name,
-        path: path.to_string_lossy().to_string(),
-        node_type,
-        size: if metadata.is_file() {
-            Some(metadata.len())
-        } else {
-            None
-        },
-        modified: metadata_timestamp(&metadata),
-        children,
-    })
-}
-
-fn read_workspace_entries(dir: &Path, root: &Path, depth: usize) -> Result<Vec<FileEntry>, String> {
-    let mut entries = Vec::new();
-    // If we fail to read the directory (e.g. Access Denied), just return empty list
-    // instead of failing the whole operation.
-    let dir_iter = match fs::read_dir(dir) {
-        Ok(iter) => iter,
-        Err(_) => return Ok(Vec::new()),
-    };
-
-    for entry in dir_iter.flatten() {
-        // Try to build entry, skip if fails (e.g. metadata access denied)
-        if let Ok(file_entry) = build_file_entry(&entry.path(), root, depth) {
-            entries.push(file_entry);
-        }
-    }
-    entries.sort_by(|a, b| {
-        let a_dir = a.node_type == "dir";
-        let b_dir = b.node_type == "dir";
-        match (a_dir, b_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        }
-    });
-    Ok(entries)
+    files::atomic::write_atomic(path, data.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
 }
 
 #[tauri::command]
-async fn read_workspace(root: String) -> Result<Vec<FileEntry>, String> {
-    let resolved = resolve_path(&root)?;
+async fn read_workspace(
+    workspace: State<'_, WorkspaceState>,
+    root: String,
+) -> Result<Vec<FileEntry>, String> {
+    let resolved = workspace.resolve_checked(&root, Access::Content)?;
     if !resolved.exists() {
         return Err(format!(
             "Workspace root {} does not exist",
@@ -270,73 +215,371 @@ async fn read_workspace(root: String) -> Result<Vec<FileEntry>, String> {
             resolved.display()
         ));
     }
-    read_workspace_entries(&resolved, &resolved, MAX_TREE_DEPTH)
+    tree::read_tree(&resolved, tree::MAX_TREE_DEPTH)
 }
 
+/// Lists one directory on demand, one page at a time.
 #[tauri::command]
-async fn read_file(path: String) -> Result<String, String> {
-    let resolved = resolve_path(&path)?;
-    if !resolved.exists() || !resolved.is_file() {
-        return Err(format!("File {} not found", resolved.display()));
-    }
+async fn read_directory(
+    workspace: State<'_, WorkspaceState>,
+    request: ReadDirectoryRequest,
+) -> Result<DirectoryPage, String> {
+    let root = workspace.resolve_checked(&request.root, Access::Content)?;
+    let dir = match &request.path {
+        Some(path) => root.join(path),
+        None => root.clone(),
+    };
+    workspace.check(&dir, Access::Content)?;
+    let limit = request.limit.unwrap_or(tree::DEFAULT_PAGE_SIZE);
+    tauri::async_runtime::spawn_blocking(move || {
+        tree::read_directory(&root, &dir, request.offset, limit)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-    let data =
-        fs::read(&resolved).map_err(|e| format!("Failed to read {}: {e}", resolved.display()))?;
+/// Reads a file together with its detected encoding, line endings and
+/// version. Passing `encoding` reopens the file with that encoding instead.
+#[tauri::command]
+async fn read_file(
+    workspace: State<'_, WorkspaceState>,
+    path: String,
+    encoding: Option<String>,
+) -> Result<TextFileContent, String> {
+    let resolved = workspace.resolve_checked(&path, Access::Content)?;
+    files::read_text(&resolved, encoding.as_deref())
+}
+
+/// Size, text/binary classification and suggested open mode of a file,
+/// without reading it whole.
+#[tauri::command]
+async fn stat_file(workspace: State<'_, WorkspaceState>, path: String) -> Result<FileInfo, String> {
+    let resolved = workspace.resolve_checked(&path, Access::Content)?;
+    tauri::async_runtime::spawn_blocking(move || files::viewer::stat(&resolved))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Reads a range of a text file, cut to character boundaries.
+#[tauri::command]
+async fn read_file_chunk(
+    workspace: State<'_, WorkspaceState>,
+    path: String,
+    offset: u64,
+    length: Option<usize>,
+    encoding: Option<String>,
+) -> Result<TextChunk, String> {
+    let resolved = workspace.resolve_checked(&path, Access::Content)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut file = fs::File::open(&resolved)
+            .map_err(|e| format!("Failed to open {}: {e}", resolved.display()))?;
+        let encoding = files::viewer::chunk_encoding(&mut file, encoding.as_deref())?;
+        let length = length.unwrap_or(files::viewer::DEFAULT_CHUNK_SIZE);
+        files::viewer::read_text_chunk(&mut file, encoding, offset, length)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-    // Use lossy conversion to handle non-UTF8 files (like system files or binary)
-    // This replaces invalid sequences with  instead of failing
-    Ok(String::from_utf8_lossy(&data).into_owned())
+#[tauri::command]
+async fn read_file_hex(
+    workspace: State<'_, WorkspaceState>,
+    path: String,
+    offset: u64,
+    length: Option<usize>,
+) -> Result<HexDump, String> {
+    let resolved = workspace.resolve_checked(&path, Access::Content)?;
+    tauri::async_runtime::spawn_blocking(move || files::viewer::hex_dump(&resolved, offset, length))
+        .await
+        .map_err(|e| e.to_string())?
 }
 
+/// Streams a file read-only, chunk by chunk, as `file-stream-chunk` events.
 #[tauri::command]
-async fn create_file(path: String) -> Result<(), String> {
-    let resolved = resolve_path(&path)?;
+async fn open_file_stream(
+    app: AppHandle,
+    workspace: State<'_, WorkspaceState>,
+    streams: State<'_, FileStreamState>,
+    path: String,
+    encoding: Option<String>,
+    chunk_size: Option<usize>,
+) -> Result<u32, String> {
+    let resolved = workspace.resolve_checked(&path, Access::Content)?;
+    let chunk_size = chunk_size.unwrap_or(files::viewer::DEFAULT_CHUNK_SIZE);
+    streams.start(&app, resolved, encoding, chunk_size)
+}
+
+/// The viewer has handled a streamed chunk; the stream may send the next.
+#[tauri::command]
+async fn ack_file_stream(
+    streams: State<'_, FileStreamState>,
+    stream_id: u32,
+) -> Result<(), String> {
+    streams.ack(stream_id);
+    Ok(())
+}
+
+#[tauri::command]
+async fn cancel_file_stream(
+    streams: State<'_, FileStreamState>,
+    stream_id: u32,
+) -> Result<(), String> {
+    streams.cancel(stream_id);
+    Ok(())
+}
+
+#[tauri::command]
+async fn create_file(
+    workspace: State<'_, WorkspaceState>,
+    journal: State<'_, FileJournalState>,
+    path: String,
+) -> Result<(), String> {
+    let resolved = workspace.resolve_checked(&path, Access::Entry)?;
+    let existed = file_ops::exists(&resolved);
     if let Some(parent) = resolved.parent() {
         fs::create_dir_all(parent)
             .map_err(|e| format!("Failed to create directory {}: {e}", parent.display()))?;
     }
     fs::File::create(&resolved)
         .map_err(|e| format!("Failed to create {}: {e}", resolved.display()))?;
+    if !existed {
+        journal.record(
+            OperationKind::Create,
+            vec![resolved.clone()],
+            vec![Change::Created(resolved)],
+        );
+    }
     Ok(())
 }
 
 #[tauri::command]
-async fn create_directory(path: String) -> Result<(), String> {
-    let resolved = resolve_path(&path)?;
+async fn create_directory(
+    workspace: State<'_, WorkspaceState>,
+    journal: State<'_, FileJournalState>,
+    path: String,
+) -> Result<(), String> {
+    let resolved = workspace.resolve_checked(&path, Access::Entry)?;
+    let existed = file_ops::exists(&resolved);
     fs::create_dir_all(&resolved)
-        .map_err(|e| format!("Failed to create directory {}: {e}", resolved.display()))
+        .map_err(|e| format!("Failed to create directory {}: {e}", resolved.display()))?;
+    if !existed {
+        journal.record(
+            OperationKind::Create,
+            vec![resolved.clone()],
+            vec![Change::Created(resolved)],
+        );
+    }
+    Ok(())
 }
 
 #[tauri::command]
-async fn rename_file(old_path: String, new_path: String) -> Result<(), String> {
-    let from = resolve_path(&old_path)?;
-    let to = resolve_path(&new_path)?;
-    if let Some(parent) = to.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create directory {}: {e}", parent.display()))?;
-    }
-    fs::rename(&from, &to).map_err(|e| format!("Failed to rename: {e}"))
+async fn rename_file(
+    workspace: State<'_, WorkspaceState>,
+    journal: State<'_, FileJournalState>,
+    old_path: String,
+    new_path: String,
+) -> Result<(), String> {
+    let from = workspace.resolve_checked(&old_path, Access::Entry)?;
+    let to = workspace.resolve_checked(&new_path, Access::Entry)?;
+    let journal = journal.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory {}: {e}", parent.display()))?;
+        }
+        // The replaced file goes to the journal's stash so undo can bring it back.
+        let mut changes = Vec::new();
+        if file_ops::exists(&to) && !file_ops::same_entry(&from, &to) {
+            match journal.stash(&to)? {
+                Some(change) => changes.push(change),
+                None => journal.forget(),
+            }
+        }
+        // rename doesn't work across file systems; file_ops then copies and deletes.
+        if let Err(e) = file_ops::move_path(&from, &to) {
+            // The rename failed: put the stashed file back.
+            if let Some(Change::Moved {
+                from: original,
+                to: slot,
+            }) = changes.pop()
+            {
+                let _ = file_ops::move_path(&slot, &original);
+            }
+            return Err(e);
+        }
+        changes.push(Change::Moved {
+            from,
+            to: to.clone(),
+        });
+        journal.record(OperationKind::Rename, vec![to], changes);
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
+/// Copies files and folders into the directory `request.destination`.
+/// Progress of large trees arrives as `file-transfer-progress` events.
 #[tauri::command]
-async fn delete_file(path: String, use_trash: bool) -> Result<(), String> {
-    let resolved = resolve_path(&path)?;
-    if use_trash {
-        trash::delete(&resolved).map_err(|e| format!("Failed to move to trash: {e}"))?;
-    } else if resolved.is_dir() {
-        fs::remove_dir_all(&resolved)
-            .map_err(|e| format!("Failed to delete {}: {e}", resolved.display()))?;
-    } else {
-        fs::remove_file(&resolved)
-            .map_err(|e| format!("Failed to delete {}: {e}", resolved.display()))?;
-    }
+async fn copy_paths(
+    app: AppHandle,
+    workspace: State<'_, WorkspaceState>,
+    journal: State<'_, FileJournalState>,
+    request: TransferRequest,
+) -> Result<Vec<TransferredPath>, String> {
+    transfer_paths(app, &workspace, &journal, Transfer::Copy, request).await
+}
 
-    Ok(())
+/// Moves files and folders into the directory `request.destination`.
+#[tauri::command]
+async fn move_paths(
+    app: AppHandle,
+    workspace: State<'_, WorkspaceState>,
+    journal: State<'_, FileJournalState>,
+    request: TransferRequest,
+) -> Result<Vec<TransferredPath>, String> {
+    transfer_paths(app, &workspace, &journal, Transfer::Move, request).await
+}
+
+async fn transfer_paths(
+    app: AppHandle,
+    workspace: &WorkspaceState,
+    journal: &FileJournalState,
+    kind: Transfer,
+    request: TransferRequest,
+) -> Result<Vec<TransferredPath>, String> {
+    // A move changes the entries themselves, a copy only reads them.
+    let access = match kind {
+        Transfer::Copy => Access::Content,
+        Transfer::Move => Access::Entry,
+    };
+    let sources = request
+        .sources
+        .iter()
+        .map(|source| workspace.resolve_checked(source, access))
+        .collect::<Result<Vec<_>, _>>()?;
+    let destination = workspace.resolve_checked(&request.destination, Access::Content)?;
+    let operation_id = request.operation_id;
+    let journal = journal.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let outcome = file_ops::transfer(
+            kind,
+            &sources,
+            &destination,
+            request.conflict,
+            &mut |progress| {
+                let _ = app.emit(
+                    "file-transfer-progress",
+                    TransferProgressEvent {
+                        operation_id: operation_id.clone(),
+                        progress: progress.clone(),
+                    },
+                );
+            },
+        );
+
+        // Whatever was transferred before an error is journaled too.
+        let journaled = journal.record_transfer(kind, &outcome.transferred);
+        match (outcome.error, journaled) {
+            (None, Ok(())) => Ok(outcome.transferred),
+            (Some(e), Ok(())) | (None, Err(e)) => Err(e),
+            (Some(e), Err(kept)) => Err(format!("{e}; {kept}")),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Copies a file or folder next to itself as `name (1).ext` and returns the
+/// new path.
+#[tauri::command]
+async fn duplicate_path(
+    workspace: State<'_, WorkspaceState>,
+    journal: State<'_, FileJournalState>,
+    path: String,
+) -> Result<String, String> {
+    let resolved = workspace.resolve_checked(&path, Access::Entry)?;
+    let target = tauri::async_runtime::spawn_blocking(move || file_ops::duplicate(&resolved))
+        .await
+        .map_err(|e| e.to_string())??;
+    journal.record(
+        OperationKind::Duplicate,
+        vec![target.clone()],
+        vec![Change::Created(target.clone())],
+    );
+    Ok(target.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+async fn delete_file(
+    workspace: State<'_, WorkspaceState>,
+    journal: State<'_, FileJournalState>,
+    path: String,
+    use_trash: bool,
+) -> Result<(), String> {
+    let resolved = workspace.resolve_checked(&path, Access::Entry)?;
+    let journal = journal.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if use_trash {
+            trash::delete(&resolved).map_err(|e| format!("Failed to move to trash: {e}"))?;
+            journal.record(
+                OperationKind::Trash,
+                vec![resolved.clone()],
+                vec![Change::Trashed(resolved)],
+            );
+            return Ok(());
+        }
+
+        // Without the trash the entry goes to the journal's stash if it is on the same device.
+        match journal.stash(&resolved)? {
+            Some(change) => journal.record(OperationKind::Delete, vec![resolved], vec![change]),
+            None => {
+                file_ops::remove_entry(&resolved)?;
+                journal.forget();
+            }
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Reverts the latest create, rename, move, copy or delete. Returns `None`
+/// when there is nothing to undo.
+#[tauri::command]
+async fn file_journal_undo(
+    journal: State<'_, FileJournalState>,
+) -> Result<Option<OperationInfo>, String> {
+    let journal = journal.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || journal.undo())
+        .await
+        .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
-async fn reveal_in_explorer(path: String) -> Result<(), String> {
-    let resolved = resolve_path(&path)?;
+async fn file_journal_redo(
+    journal: State<'_, FileJournalState>,
+) -> Result<Option<OperationInfo>, String> {
+    let journal = journal.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || journal.redo())
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Operations undo and redo would apply next, for menu labels.
+#[tauri::command]
+async fn file_journal_status(
+    journal: State<'_, FileJournalState>,
+) -> Result<JournalStatus, String> {
+    Ok(journal.status())
+}
+
+#[tauri::command]
+async fn reveal_in_explorer(
+    workspace: State<'_, WorkspaceState>,
+    path: String,
+) -> Result<(), String> {
+    let resolved = workspace.resolve_checked(&path, Access::Content)?;
     if !resolved.exists() {
         return Err(format!("File {} not found", resolved.display()));
     }
@@ -373,302 +616,471 @@ async fn reveal_in_explorer(path: String) -> Result<(), String> {
     Ok(())
 }
 
-#[derive(Debug, Clone, Deserialize)]
-struct WriteFileRequest {
-    path: String,
-    content: String,
-}
-
 #[tauri::command]
-async fn write_file(_app: AppHandle, request: WriteFileRequest) -> Result<(), String> {
-    let resolved = resolve_path(&request.path)?;
-    if let Some(parent) = resolved.parent() {
-        fs::create_dir_all(parent).map_err(|e| {
-            format!(
-                "Failed to create parent directory {}: {e}",
-                parent.display()
-            )
-        })?;
-    }
-    fs::write(&resolved, request.content.as_bytes())
-        .map_err(|e| format!("Failed to write {}: {e}", resolved.display()))?;
+async fn write_file(
+    workspace: State<'_, WorkspaceState>,
+    history: State<'_, LocalHistoryState>,
+    hot_exit: State<'_, HotExitState>,
+    _app: AppHandle,
+    request: WriteFileRequest,
+) -> Result<FileVersion, WriteFileError> {
+    let resolved = workspace.resolve_checked(&request.path, Access::Content)?;
     // Не эмитим file-changed здесь: автосохранения и обычные записи файла
     // не должны триггерить полный рефреш дерева файлов. Для структурных
     // изменений (создание/удаление/переименование) полагаемся на watcher ниже.
-    Ok(())
+    let written = files::write_text(&resolved, &request)?;
+    // The file is written already: a local history failure must not fail the save.
+    let _ = history.record_save(&resolved, written.previous.as_deref(), &written.bytes);
+    // A saved buffer is no longer dirty, so its hot exit backup is not needed.
+    let _ = hot_exit.discard(&BufferKey::File {
+        path: resolved.to_string_lossy().to_string(),
+    });
+    Ok(written.version)
 }
 
-#[tauri::command]
-async fn start_file_watcher(app: AppHandle) -> Result<(), String> {
-    let mut guard = FILE_WATCHER
-        .lock()
-        .map_err(|e| format!("Watcher lock poisoned: {e}"))?;
+// -----------------------------------------------------------------------------
+// Tauri commands: Hot exit (backups of unsaved buffers)
+// -----------------------------------------------------------------------------
 
-    if guard.is_some() {
-        return Ok(());
+/// Makes file buffer paths absolute, so the key matches the one `write_file`
+/// removes the backup by.
+fn normalize_buffer_key(workspace: &WorkspaceState, key: BufferKey) -> Result<BufferKey, String> {
+    match key {
+        BufferKey::File { path } => Ok(BufferKey::File {
+            path: workspace
+                .resolve_checked(&path, Access::Content)?
+                .to_string_lossy()
+                .to_string(),
+        }),
+        untitled => Ok(untitled),
     }
+}
 
-    let (tx, rx) = mpsc::channel::<Result<Event, notify::Error>>();
+#[tauri::command]
+async fn hot_exit_backup(
+    workspace: State<'_, WorkspaceState>,
+    hot_exit: State<'_, HotExitState>,
+    mut request: BufferBackupRequest,
+) -> Result<(), String> {
+    request.key = normalize_buffer_key(&workspace, request.key)?;
+    hot_exit.backup(request)
+}
 
-    let mut watcher = recommended_watcher(move |res| {
-        let _ = tx.send(res);
-    })
-    .map_err(|e| format!("Failed to create watcher: {e}"))?;
+#[tauri::command]
+async fn hot_exit_discard(
+    workspace: State<'_, WorkspaceState>,
+    hot_exit: State<'_, HotExitState>,
+    key: BufferKey,
+) -> Result<(), String> {
+    hot_exit.discard(&normalize_buffer_key(&workspace, key)?)
+}
 
-    let watch_path = std::env::current_dir().map_err(|e| format!("Failed to resolve cwd: {e}"))?;
-    watcher
-        .watch(&watch_path, RecursiveMode::Recursive)
-        .map_err(|e| format!("Failed to watch {}: {e}", watch_path.display()))?;
+#[tauri::command]
+async fn hot_exit_restore(
+    hot_exit: State<'_, HotExitState>,
+) -> Result<Vec<RestoredBuffer>, String> {
+    hot_exit.restore()
+}
 
-    let app_handle = app.clone();
-    thread::spawn(move || {
-        while let Ok(event_result) = rx.recv() {
-            if let Ok(event) = event_result {
-                // Фильтруем только структурные события, влияющие на дерево файлов:
-                // создание, удаление и переименование. Изменения содержимого
-                // (Modify(Data/Metadata)) игнорируем, чтобы автосейвы не
-                // перерисовывали Explorer.
-                let is_structural = matches!(
-                    &event.kind,
-                    EventKind::Create(_)
-                        | EventKind::Remove(_)
-                        | EventKind::Modify(ModifyKind::Name(_))
-                );
+// -----------------------------------------------------------------------------
+// Tauri commands: Local history (file snapshots taken on save)
+// -----------------------------------------------------------------------------
 
-                if !is_structural {
-                    continue;
-                }
+#[tauri::command]
+async fn local_history_list(
+    workspace: State<'_, WorkspaceState>,
+    history: State<'_, LocalHistoryState>,
+    path: String,
+) -> Result<Vec<HistoryEntry>, String> {
+    let resolved = workspace.resolve_checked(&path, Access::Content)?;
+    history.list(&resolved)
+}
 
-                for path in &event.paths {
-                    let _ = app_handle.emit("file-changed", path.to_string_lossy().to_string());
-                }
-            }
-        }
-    });
+#[tauri::command]
+async fn local_history_diff(
+    workspace: State<'_, WorkspaceState>,
+    history: State<'_, LocalHistoryState>,
+    path: String,
+    entry_id: String,
+) -> Result<HistoryDiff, String> {
+    let resolved = workspace.resolve_checked(&path, Access::Content)?;
+    history.diff(&resolved, &entry_id)
+}
 
-    *guard = Some(watcher);
-    Ok(())
+#[tauri::command]
+async fn local_history_restore(
+    workspace: State<'_, WorkspaceState>,
+    history: State<'_, LocalHistoryState>,
+    path: String,
+    entry_id: String,
+) -> Result<FileVersion, String> {
+    let resolved = workspace.resolve_checked(&path, Access::Content)?;
+    history.restore(&resolved, &entry_id)
 }
 
-// -----------------------------------------------------------------------------
-// Search: Project-wide file search with regex support
-// -----------------------------------------------------------------------------
+#[tauri::command]
+async fn start_file_watcher(
+    app: AppHandle,
+    watcher: State<'_, WatcherState>,
+) -> Result<(), String> {
+    watcher.start(&app, &watched_roots(&app), dispatch_file_changes)
+}
 
-/// Search request payload
-#[derive(Debug, Clone, Deserialize)]
-struct SearchFilesRequest {
-    root: String,
-    query: String,
-    use_regex: bool,
-    case_sensitive: bool,
+/// Stops the watcher and waits for its thread to end.
+#[tauri::command]
+async fn stop_file_watcher(app: AppHandle, watcher: State<'_, WatcherState>) -> Result<(), String> {
+    watcher.stop(&app);
+    Ok(())
 }
 
-/// Global flag for cancelling active search operations
-static SEARCH_CANCELLED: Lazy<Arc<AtomicBool>> = Lazy::new(|| Arc::new(AtomicBool::new(false)));
+/// Recreates the watcher on the current roots, starting it if it was stopped.
+#[tauri::command]
+async fn restart_file_watcher(
+    app: AppHandle,
+    watcher: State<'_, WatcherState>,
+) -> Result<WatcherStatus, String> {
+    watcher.restart(&app, &watched_roots(&app), dispatch_file_changes)
+}
 
+/// Points the watcher at the given folders instead of the workspace roots.
+/// The next change of the workspace roots points it back at them.
 #[tauri::command]
-async fn cancel_search() -> Result<(), String> {
-    SEARCH_CANCELLED.store(true, Ordering::Relaxed);
-    Ok(())
+async fn retarget_file_watcher(
+    app: AppHandle,
+    workspace: State<'_, WorkspaceState>,
+    watcher: State<'_, WatcherState>,
+    roots: Vec<String>,
+) -> Result<WatcherStatus, String> {
+    let resolved = roots
+        .iter()
+        .map(|root| workspace.resolve_checked(root, Access::Content))
+        .collect::<Result<Vec<_>, _>>()?;
+    watcher.restart(&app, &resolved, dispatch_file_changes)
+}
+
+#[tauri::command]
+async fn file_watcher_status(watcher: State<'_, WatcherState>) -> Result<WatcherStatus, String> {
+    Ok(watcher.status())
 }
 
+/// Sets the mode (auto/native/polling) and the poll interval; a running
+/// watcher is restarted with the new configuration.
 #[tauri::command]
-async fn search_files(app: AppHandle, request: SearchFilesRequest) -> Result<(), String> {
-    // Reset cancellation flag
-    SEARCH_CANCELLED.store(false, Ordering::Relaxed);
+async fn file_watcher_configure(
+    app: AppHandle,
+    watcher: State<'_, WatcherState>,
+    config: WatcherConfig,
+) -> Result<WatcherStatus, String> {
+    watcher.configure(&app, config, &watched_roots(&app), dispatch_file_changes)
+}
 
-    let root_path = resolve_path(&request.root)?;
-    if !root_path.exists() || !root_path.is_dir() {
-        return Err(format!(
-            "Search root {} is not a directory",
-            root_path.display()
-        ));
+/// Everything the watcher has to see: the workspace roots, and the root of
+/// the git repository when it lies outside them (a workspace opened on a
+/// subfolder of the repository).
+fn watched_roots(app: &AppHandle) -> Vec<PathBuf> {
+    let mut roots = app.state::<WorkspaceState>().roots();
+    if let Some(repo_root) = app.state::<GitState>().repository_root() {
+        roots.push(repo_root);
     }
+    roots
+}
 
-    let query = request.query.clone();
-    let use_regex = request.use_regex;
-    let case_sensitive = request.case_sensitive;
+/// Re-targets a running watcher after the workspace roots or the repository changed.
+fn sync_file_watcher(app: &AppHandle) -> Result<(), String> {
+    app.state::<WatcherState>()
+        .retarget(app, &watched_roots(app), dispatch_file_changes)
+}
 
-    // Spawn search in background thread to avoid blocking
-    thread::spawn(move || {
-        let _ = perform_search(app, root_path, query, use_regex, case_sensitive);
-    });
+/// Hands each watcher batch to its subscribers: the quick open and symbol
+/// indexes, git and the frontend (the Explorer listens to `file-changes`).
+fn dispatch_file_changes(app: &AppHandle, batch: &FileChangeBatch) {
+    // Frontend and git first: updating the indexes may take a while.
+    let _ = app.emit("file-changes", batch);
+    app.state::<GitState>().apply_changes(app, batch);
+    app.state::<QuickOpenState>().apply_changes(batch);
+    app.state::<SymbolState>().apply_changes(app, batch);
+}
 
-    Ok(())
+// -----------------------------------------------------------------------------
+// Workspace: root folders every path is resolved against
+// -----------------------------------------------------------------------------
+
+/// Applies a change of the root set: drops indexes of removed roots,
+/// re-targets the watcher and notifies the frontend.
+fn workspace_roots_changed(app: &AppHandle, workspace: &WorkspaceState) -> Result<(), String> {
+    let roots = workspace.roots();
+    app.state::<QuickOpenState>().retain_roots(&roots);
+    app.state::<SymbolState>().retain_roots(&roots);
+    let _ = app.emit("workspace-roots-changed", workspace.info());
+    sync_file_watcher(app)
 }
 
-fn perform_search(
+/// Asks the user, in a native dialog the webview can't fake, whether a path
+/// outside the workspace may be accessed. Once confirmed, file commands accept
+/// the path and everything below it for the rest of the session.
+#[tauri::command]
+async fn workspace_request_access(
     app: AppHandle,
-    root: PathBuf,
-    query: String,
-    use_regex: bool,
-    case_sensitive: bool,
-) -> Result<(), String> {
-    // Compile regex if needed
-    let regex_pattern = if use_regex {
-        let pattern = if case_sensitive {
-            query.clone()
-        } else {
-            format!("(?i){}", query)
-        };
-        match Regex::new(&pattern) {
-            Ok(re) => Some(re),
-            Err(e) => {
-                let _ = app.emit("search-error", format!("Invalid regex: {}", e));
-                return Err(format!("Invalid regex: {}", e));
-            }
-        }
+    workspace: State<'_, WorkspaceState>,
+    path: String,
+) -> Result<bool, String> {
+    let resolved = workspace.resolve(&path)?;
+    let message = format!(
+        "Nova Code wants to access {}, which is outside the open workspace.",
+        resolved.display()
+    );
+    let confirmed = tauri::async_runtime::spawn_blocking(move || {
+        app.dialog()
+            .message(message)
+            .title("Allow access outside the workspace?")
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancel)
+            .blocking_show()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if confirmed {
+        workspace.grant(&resolved)?;
+    }
+    Ok(confirmed)
+}
+
+#[tauri::command]
+async fn workspace_get_roots(
+    workspace: State<'_, WorkspaceState>,
+) -> Result<Vec<WorkspaceRoot>, String> {
+    Ok(workspace.info())
+}
+
+/// Lets the user pick a folder in the native dialog and opens it: as the
+/// only root, or next to the current ones with `add`. Returns `None` when the
+/// dialog was cancelled. This is the only way a new folder becomes a root.
+#[tauri::command]
+async fn workspace_pick_folder(
+    app: AppHandle,
+    workspace: State<'_, WorkspaceState>,
+    add: bool,
+) -> Result<Option<Vec<WorkspaceRoot>>, String> {
+    let dialog = app.clone();
+    let title = if add {
+        "Add Folder to Workspace"
     } else {
-        None
+        "Open Folder"
     };
+    let picked = tauri::async_runtime::spawn_blocking(move || {
+        dialog
+            .dialog()
+            .file()
+            .set_title(title)
+            .blocking_pick_folder()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+    let folder = picked.into_path().map_err(|e| e.to_string())?;
 
-    // Prepare simple string search
-    let search_query = if !use_regex && !case_sensitive {
-        query.to_lowercase()
+    workspace.record_pick(&folder)?;
+    let changed = if add {
+        workspace.add_root(&folder)?
     } else {
-        query.clone()
+        workspace.set_roots(&[&folder])?
     };
+    if changed {
+        workspace_roots_changed(&app, &workspace)?;
+    }
+    Ok(Some(workspace.info()))
+}
 
-    // Walk directory tree
-    let walker = walkdir::WalkDir::new(&root)
-        .follow_links(false)
-        .max_depth(10)
-        .into_iter()
-        .filter_entry(|e| {
-            // Skip hidden directories and common excludes
-            let file_name = e.file_name().to_string_lossy();
-            !file_name.starts_with('.')
-                && file_name != "node_modules"
-                && file_name != "target"
-                && file_name != "dist"
-                && file_name != "build"
-        });
+/// Replaces the workspace roots; the first one becomes the primary root.
+/// Only folders picked in `workspace_pick_folder` are accepted, so this
+/// can reorder or close roots but not open new ones.
+#[tauri::command]
+async fn workspace_set_roots(
+    app: AppHandle,
+    workspace: State<'_, WorkspaceState>,
+    roots: Vec<String>,
+) -> Result<Vec<WorkspaceRoot>, String> {
+    if workspace.set_roots(&roots)? {
+        workspace_roots_changed(&app, &workspace)?;
+    }
+    Ok(workspace.info())
+}
 
-    let mut result_count = 0;
-    const MAX_RESULTS: usize = 1000;
+#[tauri::command]
+async fn workspace_add_root(
+    app: AppHandle,
+    workspace: State<'_, WorkspaceState>,
+    root: String,
+) -> Result<Vec<WorkspaceRoot>, String> {
+    if workspace.add_root(Path::new(&root))? {
+        workspace_roots_changed(&app, &workspace)?;
+    }
+    Ok(workspace.info())
+}
 
-    for entry in walker {
-        // Check if search was cancelled
-        if SEARCH_CANCELLED.load(Ordering::Relaxed) {
-            let _ = app.emit("search-cancelled", ());
-            return Ok(());
-        }
+#[tauri::command]
+async fn workspace_remove_root(
+    app: AppHandle,
+    workspace: State<'_, WorkspaceState>,
+    root: String,
+) -> Result<Vec<WorkspaceRoot>, String> {
+    if workspace.remove_root(&root) {
+        workspace_roots_changed(&app, &workspace)?;
+    }
+    Ok(workspace.info())
+}
 
-        let entry = match entry {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
+// -----------------------------------------------------------------------------
+// Quick open: fuzzy file finder over a cached workspace index
+// -----------------------------------------------------------------------------
 
-        // Only search files
-        if !entry.file_type().is_file() {
-            continue;
+/// Builds (or returns the cached) file index for a workspace root.
+#[tauri::command]
+async fn quick_open_index(
+    workspace: State<'_, WorkspaceState>,
+    quick_open: State<'_, QuickOpenState>,
+    root: String,
+    rebuild: Option<bool>,
+) -> Result<QuickOpenIndexInfo, String> {
+    let root_path = workspace.resolve_checked(&root, Access::Content)?;
+    let state = quick_open.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if rebuild.unwrap_or(false) {
+            state.rebuild(&root_path)
+        } else {
+            state.ensure_index(&root_path)
         }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-        let path = entry.path();
-
-        // Skip binary files by extension
-        if let Some(ext) = path.extension() {
-            let ext_str = ext.to_string_lossy().to_lowercase();
-            if matches!(
-                ext_str.as_str(),
-                "png"
-                    | "jpg"
-                    | "jpeg"
-                    | "gif"
-                    | "ico"
-                    | "woff"
-                    | "woff2"
-                    | "ttf"
-                    | "eot"
-                    | "exe"
-                    | "dll"
-                    | "so"
-                    | "dylib"
-            ) {
-                continue;
-            }
-        }
+#[tauri::command]
+async fn quick_open_query(
+    workspace: State<'_, WorkspaceState>,
+    quick_open: State<'_, QuickOpenState>,
+    request: QuickOpenRequest,
+) -> Result<Vec<QuickOpenMatch>, String> {
+    let root_path = workspace.resolve_checked(&request.root, Access::Content)?;
+    let state = quick_open.inner().clone();
+    let limit = request.limit.unwrap_or(quick_open::DEFAULT_LIMIT);
+    tauri::async_runtime::spawn_blocking(move || state.query(&root_path, &request.query, limit))
+        .await
+        .map_err(|e| e.to_string())?
+}
 
-        // Try to read file as UTF-8
-        let file = match fs::File::open(path) {
-            Ok(f) => f,
-            Err(_) => continue,
-        };
-
-        let reader = BufReader::new(file);
-        let relative_path = path
-            .strip_prefix(&root)
-            .unwrap_or(path)
-            .to_string_lossy()
-            .replace('\\', "/");
-
-        // Search line by line
-        for (line_num, line_result) in reader.lines().enumerate() {
-            if SEARCH_CANCELLED.load(Ordering::Relaxed) {
-                let _ = app.emit("search-cancelled", ());
-                return Ok(());
-            }
+// -----------------------------------------------------------------------------
+// Symbols: workspace-wide symbol index for go-to-symbol and the outline
+// -----------------------------------------------------------------------------
 
-            let line = match line_result {
-                Ok(l) => l,
-                Err(_) => break, // Skip non-UTF8 files
-            };
-
-            let line_number = (line_num + 1) as u32;
-
-            // Perform search
-            let matches = if let Some(ref re) = regex_pattern {
-                re.find_iter(&line)
-                    .map(|mat| (mat.start(), mat.end() - mat.start()))
-                    .collect::<Vec<_>>()
-            } else {
-                // Simple string search
-                let search_in = if case_sensitive {
-                    line.clone()
-                } else {
-                    line.to_lowercase()
-                };
-
-                let mut matches = Vec::new();
-                let mut start = 0;
-                while let Some(pos) = search_in[start..].find(&search_query) {
-                    let absolute_pos = start + pos;
-                    matches.push(absolute_pos);
-                    start = absolute_pos + search_query.len();
-                }
-                matches
-                    .into_iter()
-                    .map(|pos| (pos, search_query.len()))
-                    .collect::<Vec<_>>()
-            };
-
-            // Emit results
-            for (pos, len) in matches {
-                let match_text = line.chars().skip(pos).take(len).collect::<String>();
-
-                let hit = SearchHit {
-                    file: relative_path.clone(),
-                    line: line_number,
-                    column: (pos + 1) as u32,
-                    match_text,
-                    line_text: line.clone(),
-                };
-
-                let _ = app.emit("search-hit", &hit);
-                result_count += 1;
-
-                if result_count >= MAX_RESULTS {
-                    let _ = app.emit("search-complete", result_count);
-                    return Ok(());
-                }
-            }
-        }
+/// Starts indexing a workspace root in the background and returns its
+/// current status. Progress arrives as `symbol-index-progress` events.
+#[tauri::command]
+async fn symbol_index_start(
+    workspace: State<'_, WorkspaceState>,
+    app: AppHandle,
+    symbols: State<'_, SymbolState>,
+    root: String,
+    rebuild: Option<bool>,
+) -> Result<SymbolIndexStatus, String> {
+    let root_path = workspace.resolve_checked(&root, Access::Content)?;
+    if !root_path.is_dir() {
+        return Err(format!(
+            "Workspace root {} is not a directory",
+            root_path.display()
+        ));
     }
+    Ok(symbols.start_indexing(&app, &root_path, rebuild.unwrap_or(false)))
+}
 
-    let _ = app.emit("search-complete", result_count);
+#[tauri::command]
+async fn symbol_index_status(
+    workspace: State<'_, WorkspaceState>,
+    symbols: State<'_, SymbolState>,
+    root: String,
+) -> Result<SymbolIndexStatus, String> {
+    let root_path = workspace.resolve_checked(&root, Access::Content)?;
+    Ok(symbols.status(&root_path))
+}
+
+#[tauri::command]
+async fn symbol_query(
+    workspace: State<'_, WorkspaceState>,
+    symbols: State<'_, SymbolState>,
+    request: SymbolQueryRequest,
+) -> Result<Vec<SymbolMatch>, String> {
+    let root_path = workspace.resolve_checked(&request.root, Access::Content)?;
+    let state = symbols.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || state.query(&root_path, &request))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// -----------------------------------------------------------------------------
+// Search: Project-wide file search with regex support
+// -----------------------------------------------------------------------------
+
+/// Cancels one search, or every running search when no id is given.
+#[tauri::command]
+async fn cancel_search(
+    search_state: State<'_, SearchState>,
+    search_id: Option<String>,
+) -> Result<(), String> {
+    match search_id {
+        Some(id) => search_state.cancel(&id),
+        None => search_state.cancel_all(),
+    }
     Ok(())
 }
 
+/// Starts a search in the background and returns its id. Events of the
+/// search are tagged with that id.
+#[tauri::command]
+async fn search_files(
+    workspace: State<'_, WorkspaceState>,
+    app: AppHandle,
+    search_state: State<'_, SearchState>,
+    request: SearchFilesRequest,
+) -> Result<String, String> {
+    let root_path = workspace.resolve_checked(&request.root, Access::Content)?;
+    if !root_path.exists() || !root_path.is_dir() {
+        return Err(format!(
+            "Search root {} is not a directory",
+            root_path.display()
+        ));
+    }
+
+    let (search_id, token) = search_state.begin(request.search_id.clone());
+    let state = search_state.inner().clone();
+    let ctx = SearchContext::new(app, search_id.clone(), Arc::clone(&token));
+    let finished_id = search_id.clone();
+
+    // Spawn search in background thread to avoid blocking
+    thread::spawn(move || {
+        let _ = search::perform_search(&ctx, root_path, request);
+        state.finish(&finished_id, &token);
+    });
+
+    Ok(search_id)
+}
+
+#[tauri::command]
+async fn search_replace_apply(
+    workspace: State<'_, WorkspaceState>,
+    request: ApplyReplaceRequest,
+) -> Result<ReplaceSummary, String> {
+    let root_path = workspace.resolve_checked(&request.root, Access::Content)?;
+    let workspace = workspace.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        search::replace::apply_replacements(&workspace, &root_path, &request)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 // -----------------------------------------------------------------------------
 // Tauri commands: Profiles
 // -----------------------------------------------------------------------------
@@ -795,7 +1207,7 @@ fn map_git_error(err: git::GitError) -> String {
     match err {
         git::GitError::NoRepository => "Git repository is not available".to_string(),
         git::GitError::InvalidInput(msg) => msg,
-        git::GitError::Git(msg) | git::GitError::Io(msg) | git::GitError::Notify(msg) => msg,
+        git::GitError::Git(msg) | git::GitError::Io(msg) => msg,
     }
 }
 
@@ -812,11 +1224,12 @@ fn git_op_post(app: &AppHandle, git_state: &GitState) {
 
 #[tauri::command]
 async fn git_detect_repository(
+    workspace: State<'_, WorkspaceState>,
     app: AppHandle,
     git_state: State<'_, GitState>,
     root: String,
 ) -> Result<Option<String>, String> {
-    let resolved = resolve_path(&root)?;
+    let resolved = workspace.resolve_checked(&root, Access::Content)?;
     let detected = tauri::async_runtime::spawn_blocking(move || git::detect_repository(&resolved))
         .await
         .map_err(|e| e.to_string())?
@@ -824,38 +1237,37 @@ async fn git_detect_repository(
 
     if let Some(repo_root) = detected.clone() {
         git_state.set_repository_root(Some(repo_root.clone()));
-        git_state.ensure_watcher(&app).map_err(map_git_error)?;
+        sync_file_watcher(&app)?;
         git_state.emit_status_changed(&app);
         Ok(Some(repo_root.to_string_lossy().replace('\\', "/")))
     } else {
         git_state.set_repository_root(None);
+        sync_file_watcher(&app)?;
         Ok(None)
     }
 }
 
 #[tauri::command]
 async fn git_init(
+    workspace: State<'_, WorkspaceState>,
     app: AppHandle,
     git_state: State<'_, GitState>,
     root: String,
 ) -> Result<(), String> {
-    let resolved = resolve_path(&root)?;
+    let resolved = workspace.resolve_checked(&root, Access::Content)?;
     let repo_root = tauri::async_runtime::spawn_blocking(move || git::init_repository(&resolved))
         .await
         .map_err(|e| e.to_string())?
         .map_err(map_git_error)?;
 
     git_state.set_repository_root(Some(repo_root));
-    git_state.ensure_watcher(&app).map_err(map_git_error)?;
+    sync_file_watcher(&app)?;
     git_state.emit_status_changed(&app);
     Ok(())
 }
 
 #[tauri::command]
-async fn git_get_status(
-    app: AppHandle,
-    git_state: State<'_, GitState>,
-) -> Result<GitRepositoryStatus, String> {
+async fn git_get_status(git_state: State<'_, GitState>) -> Result<GitRepositoryStatus, String> {
     if let Some(cached) = git_state.get_cached_status() {
         return Ok(cached);
     }
@@ -865,7 +1277,6 @@ async fn git_get_status(
         .map_err(|e| e.to_string())?
         .map_err(map_git_error)?;
     git_state.store_status_cache(status.clone());
-    git_state.ensure_watcher(&app).map_err(map_git_error)?;
     Ok(status)
 }
 
@@ -1016,6 +1427,126 @@ async fn git_get_staged_diff(
         .map_err(map_git_error)
 }
 
+// -----------------------------------------------------------------------------
+// Terminal commands
+// -----------------------------------------------------------------------------
+
+fn map_terminal_error(err: terminal::TerminalError) -> String {
+    match err {
+        terminal::TerminalError::NotFound(id) => format!("Terminal session {id} not found"),
+        terminal::TerminalError::InvalidInput(msg) => msg,
+        terminal::TerminalError::Pty(msg) | terminal::TerminalError::Io(msg) => msg,
+    }
+}
+
+#[tauri::command]
+async fn terminal_create(
+    workspace: State<'_, WorkspaceState>,
+    app: AppHandle,
+    terminal_state: State<'_, TerminalState>,
+    request: CreateTerminalRequest,
+) -> Result<TerminalInfo, String> {
+    let cwd = workspace.resolve_checked(&request.cwd, Access::Content)?;
+    terminal_state
+        .create(&app, &cwd, &request)
+        .map_err(map_terminal_error)
+}
+
+#[tauri::command]
+async fn terminal_write(
+    terminal_state: State<'_, TerminalState>,
+    id: u32,
+    data: String,
+) -> Result<(), String> {
+    terminal_state.write(id, &data).map_err(map_terminal_error)
+}
+
+#[tauri::command]
+async fn terminal_resize(
+    terminal_state: State<'_, TerminalState>,
+    id: u32,
+    cols: u16,
+    rows: u16,
+) -> Result<(), String> {
+    terminal_state
+        .resize(id, cols, rows)
+        .map_err(map_terminal_error)
+}
+
+#[tauri::command]
+async fn terminal_kill(terminal_state: State<'_, TerminalState>, id: u32) -> Result<(), String> {
+    terminal_state.kill(id).map_err(map_terminal_error)
+}
+
+#[tauri::command]
+async fn terminal_list(
+    terminal_state: State<'_, TerminalState>,
+) -> Result<Vec<TerminalInfo>, String> {
+    Ok(terminal_state.list())
+}
+
+// -----------------------------------------------------------------------------
+// Language server commands
+// -----------------------------------------------------------------------------
+
+#[tauri::command]
+async fn lsp_start(
+    workspace: State<'_, WorkspaceState>,
+    app: AppHandle,
+    lsp_state: State<'_, LspState>,
+    request: StartLspRequest,
+) -> Result<LspServerInfo, String> {
+    let root = workspace.resolve_checked(&request.root, Access::Content)?;
+    lsp_state
+        .start(&app, &root, &request.language_id, request.command)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn lsp_start_for_file(
+    workspace: State<'_, WorkspaceState>,
+    app: AppHandle,
+    lsp_state: State<'_, LspState>,
+    root: String,
+    path: String,
+) -> Result<LspServerInfo, String> {
+    let root = workspace.resolve_checked(&root, Access::Content)?;
+    let language_id = git::diff::language_from_path(Path::new(&path));
+    lsp_state
+        .start(&app, &root, &language_id, None)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn lsp_send(
+    lsp_state: State<'_, LspState>,
+    server_id: u32,
+    message: serde_json::Value,
+) -> Result<(), String> {
+    lsp_state
+        .send(server_id, &message)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn lsp_stop(lsp_state: State<'_, LspState>, server_id: u32) -> Result<(), String> {
+    let state = lsp_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || state.stop(server_id))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn lsp_list(lsp_state: State<'_, LspState>) -> Result<Vec<LspServerInfo>, String> {
+    Ok(lsp_state.list())
+}
+
+#[tauri::command]
+async fn lsp_language_id(path: String) -> Result<String, String> {
+    Ok(git::diff::language_from_path(Path::new(&path)))
+}
+
 // -----------------------------------------------------------------------------
 // App entry
 // -----------------------------------------------------------------------------
@@ -1026,19 +1557,81 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .manage::<AppPathsState>(AppPathsState)
+        .manage::<WorkspaceState>(WorkspaceState::default())
+        .manage::<LocalHistoryState>(LocalHistoryState::default())
+        .manage::<HotExitState>(HotExitState::default())
+        .manage::<FileJournalState>(FileJournalState::default())
+        .setup(|app| {
+            // Settings and profiles live in app_config_dir: no confirmation needed.
+            if let Ok(paths) = AppPaths::new(app.handle()) {
+                app.state::<WorkspaceState>().allow(&paths.config_dir);
+                // Without its store local history is just unavailable; startup goes on.
+                let _ = app
+                    .state::<LocalHistoryState>()
+                    .open(paths.file("local-history"));
+                let _ = app.state::<HotExitState>().open(paths.file("backups"));
+                let _ = app
+                    .state::<FileJournalState>()
+                    .open(paths.file("file-journal"));
+            }
+            Ok(())
+        })
+        .manage::<FileStreamState>(FileStreamState::default())
         .manage::<GitState>(GitState::default())
+        .manage::<TerminalState>(TerminalState::default())
+        .manage::<LspState>(LspState::default())
+        .manage::<SearchState>(SearchState::default())
+        .manage::<QuickOpenState>(QuickOpenState::default())
+        .manage::<SymbolState>(SymbolState::default())
+        .manage::<WatcherState>(WatcherState::default())
         .invoke_handler(tauri::generate_handler![
             read_workspace,
+            read_directory,
             read_file,
+            stat_file,
+            read_file_chunk,
+            read_file_hex,
+            open_file_stream,
+            ack_file_stream,
+            cancel_file_stream,
             create_file,
             create_directory,
             rename_file,
+            copy_paths,
+            move_paths,
+            duplicate_path,
             delete_file,
+            file_journal_undo,
+            file_journal_redo,
+            file_journal_status,
             reveal_in_explorer,
             write_file,
+            local_history_list,
+            local_history_diff,
+            local_history_restore,
+            hot_exit_backup,
+            hot_exit_discard,
+            hot_exit_restore,
             start_file_watcher,
+            stop_file_watcher,
+            restart_file_watcher,
+            retarget_file_watcher,
+            file_watcher_status,
+            file_watcher_configure,
+            workspace_get_roots,
+            workspace_pick_folder,
+            workspace_set_roots,
+            workspace_add_root,
+            workspace_remove_root,
+            workspace_request_access,
+            quick_open_index,
+            quick_open_query,
+            symbol_index_start,
+            symbol_index_status,
+            symbol_query,
             search_files,
             cancel_search,
+            search_replace_apply,
             settings_profiles_load,
             settings_profiles_save,
             settings_history_load,
@@ -1059,10 +1652,31 @@ pub fn run() {
             git_commit,
             git_get_history,
             git_get_file_diff,
-            git_get_staged_diff
+            git_get_staged_diff,
+            terminal_create,
+            terminal_write,
+            terminal_resize,
+            terminal_kill,
+            terminal_list,
+            lsp_start,
+            lsp_start_for_file,
+            lsp_send,
+            lsp_stop,
+            lsp_list,
+            lsp_language_id
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                app.state::<TerminalState>().kill_all();
+                app.state::<LspState>().stop_all();
+                app.state::<SearchState>().cancel_all();
+                app.state::<FileStreamState>().cancel_all();
+                app.state::<WatcherState>().stop(app);
+                let _ = app.state::<LocalHistoryState>().flush();
+            }
+        });
 }
 
 // State wrapper (lazy-init AppPaths using AppHandle)
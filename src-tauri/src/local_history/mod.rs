@@ -0,0 +1,145 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+pub mod store;
+pub mod types;
+
+use crate::{
+    files::{atomic::write_atomic, encoding, types::FileVersion, version::version_of},
+    git::diff::language_from_path,
+};
+use store::HistoryStore;
+use types::{HistoryDiff, HistoryEntry, SnapshotSource};
+
+/// How long after a save the index is written. Saves in between (auto-save
+/// while typing) share the write.
+const FLUSH_DELAY: Duration = Duration::from_secs(2);
+
+/// Local file history: a timeline of snapshots per file, taken on save,
+/// independent of any version control. Stays unavailable until `open` is
+/// called with the storage directory.
+#[derive(Clone, Default)]
+pub struct LocalHistoryState {
+    store: Arc<Mutex<Option<HistoryStore>>>,
+    /// Snapshots were added that the index on disk doesn't list yet.
+    flush_due: Arc<AtomicBool>,
+}
+
+impl LocalHistoryState {
+    pub fn open(&self, dir: PathBuf) -> Result<(), String> {
+        let store = HistoryStore::open(dir)?;
+        *self.store.lock().expect("local history poisoned") = Some(store);
+        Ok(())
+    }
+
+    fn with_store<T>(
+        &self,
+        f: impl FnOnce(&mut HistoryStore) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut guard = self.store.lock().expect("local history poisoned");
+        let store = guard
+            .as_mut()
+            .ok_or_else(|| "Local history is not available".to_string())?;
+        f(store)
+    }
+
+    /// Records a save of `path`: the contents it replaced, when the history
+    /// has not seen them, and the contents written. The index is written
+    /// `FLUSH_DELAY` later on a thread of its own.
+    pub fn record_save(
+        &self,
+        path: &Path,
+        previous: Option<&[u8]>,
+        saved: &[u8],
+    ) -> Result<(), String> {
+        let result = self.with_store(|store| {
+            if let Some(previous) = previous {
+                store.add(path, previous, SnapshotSource::External)?;
+            }
+            store.add(path, saved, SnapshotSource::Save)
+        });
+        if result.is_ok() && !self.flush_due.swap(true, Ordering::AcqRel) {
+            let state = self.clone();
+            thread::spawn(move || {
+                thread::sleep(FLUSH_DELAY);
+                let _ = state.flush();
+            });
+        }
+        result
+    }
+
+    /// Writes the index if saves were recorded since it was last written.
+    /// Called on exit for saves whose delayed write hasn't run yet.
+    pub fn flush(&self) -> Result<(), String> {
+        // Cleared first: a save during the write schedules another one.
+        if !self.flush_due.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+        self.with_store(HistoryStore::flush)
+    }
+
+    /// Timeline of `path`, newest first.
+    pub fn list(&self, path: &Path) -> Result<Vec<HistoryEntry>, String> {
+        self.with_store(|store| Ok(store.entries(path)))
+    }
+
+    /// The snapshot `id` of `path` against the file as it is now.
+    pub fn diff(&self, path: &Path, id: &str) -> Result<HistoryDiff, String> {
+        let (entry, snapshot) = self.with_store(|store| {
+            let entry = store.find(path, id)?;
+            let bytes = store.read(&entry)?;
+            Ok((entry, bytes))
+        })?;
+        let current = fs::read(path).ok().unwrap_or_default();
+        let binary =
+            !encoding::looks_text(prefix(&snapshot)) || !encoding::looks_text(prefix(&current));
+
+        Ok(HistoryDiff {
+            path: path.to_string_lossy().replace('\\', "/"),
+            entry,
+            old_content: encoding::decode(&snapshot, None).text,
+            new_content: encoding::decode(&current, None).text,
+            language: language_from_path(path),
+            binary,
+        })
+    }
+
+    /// Writes the snapshot `id` back to `path`, byte for byte. The contents
+    /// it replaces are snapshotted first, so a restore can be undone from
+    /// the timeline as well.
+    pub fn restore(&self, path: &Path, id: &str) -> Result<FileVersion, String> {
+        self.with_store(|store| {
+            let entry = store.find(path, id)?;
+            let bytes = store.read(&entry)?;
+            if let Ok(current) = fs::read(path) {
+                store.add(path, &current, SnapshotSource::External)?;
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| {
+                    format!(
+                        "Failed to create parent directory {}: {e}",
+                        parent.display()
+                    )
+                })?;
+            }
+            write_atomic(path, &bytes)
+                .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+            store.add(path, &bytes, SnapshotSource::Restore)?;
+            store.flush()?;
+            Ok(version_of(&bytes, fs::metadata(path).ok().as_ref()))
+        })
+    }
+}
+
+/// Enough of the contents to tell text from binary.
+fn prefix(bytes: &[u8]) -> &[u8] {
+    &bytes[..bytes.len().min(8192)]
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    files::{atomic::write_atomic, version::content_hash},
+    local_history::types::{HistoryEntry, SnapshotSource},
+};
+
+const INDEX_FILE: &str = "index.json";
+const BLOBS_DIR: &str = "blobs";
+
+/// Snapshots older than this are dropped.
+pub const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// Storage for all snapshots together; the oldest go first beyond it.
+pub const MAX_TOTAL_SIZE: u64 = 256 * 1024 * 1024;
+pub const MAX_ENTRIES_PER_FILE: usize = 100;
+/// Larger files are not snapshotted at all.
+pub const MAX_SNAPSHOT_SIZE: u64 = 16 * 1024 * 1024;
+
+#[derive(Default, Serialize, Deserialize)]
+struct Index {
+    /// Timelines keyed by absolute path, oldest entry first.
+    files: BTreeMap<String, Vec<HistoryEntry>>,
+}
+
+/// Snapshots on disk: `index.json` holds the timelines, `blobs/<hash>` the
+/// contents, stored once per distinct hash.
+pub struct HistoryStore {
+    dir: PathBuf,
+    index: Index,
+}
+
+impl HistoryStore {
+    /// Opens the store in `dir`, creating it if needed. An unreadable index
+    /// starts an empty history rather than failing.
+    pub fn open(dir: PathBuf) -> Result<Self, String> {
+        fs::create_dir_all(dir.join(BLOBS_DIR))
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        let index = fs::read(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        let mut store = Self { dir, index };
+        store.prune(now_millis());
+        store.collect_garbage();
+        Ok(store)
+    }
+
+    /// Timeline of `path`, newest first.
+    pub fn entries(&self, path: &Path) -> Vec<HistoryEntry> {
+        self.index
+            .files
+            .get(&key(path))
+            .map(|entries| entries.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn find(&self, path: &Path, id: &str) -> Result<HistoryEntry, String> {
+        self.index
+            .files
+            .get(&key(path))
+            .and_then(|entries| entries.iter().find(|entry| entry.id == id))
+            .cloned()
+            .ok_or_else(|| format!("No snapshot {id} for {}", path.display()))
+    }
+
+    pub fn read(&self, entry: &HistoryEntry) -> Result<Vec<u8>, String> {
+        fs::read(self.blob_path(&entry.hash))
+            .map_err(|e| format!("Failed to read snapshot {}: {e}", entry.id))
+    }
+
+    /// Adds `bytes` to the timeline of `path`, unless they equal its latest
+    /// snapshot or are too large. Takes effect on disk with `flush`.
+    pub fn add(&mut self, path: &Path, bytes: &[u8], source: SnapshotSource) -> Result<(), String> {
+        if bytes.len() as u64 > MAX_SNAPSHOT_SIZE {
+            return Ok(());
+        }
+        let hash = content_hash(bytes);
+        let entries = self.index.files.entry(key(path)).or_default();
+        if entries.last().is_some_and(|last| last.hash == hash) {
+            return Ok(());
+        }
+
+        let blob = self.dir.join(BLOBS_DIR).join(&hash);
+        if !blob.exists() {
+            write_atomic(&blob, bytes).map_err(|e| format!("Failed to store snapshot: {e}"))?;
+        }
+        let timestamp = now_millis();
+        entries.push(HistoryEntry {
+            id: format!("{timestamp}-{}", &hash[..12]),
+            timestamp,
+            size: bytes.len() as u64,
+            hash,
+            source,
+        });
+        Ok(())
+    }
+
+    /// Applies the expiry limits and writes the index.
+    pub fn flush(&mut self) -> Result<(), String> {
+        if self.prune(now_millis()) {
+            self.collect_garbage();
+        }
+        let json = serde_json::to_vec(&self.index)
+            .map_err(|e| format!("Failed to serialize history index: {e}"))?;
+        write_atomic(&self.dir.join(INDEX_FILE), &json)
+            .map_err(|e| format!("Failed to write history index: {e}"))
+    }
+
+    /// Drops snapshots past the age and per-file limits, then the oldest
+    /// ones until the total size fits. Returns whether any were dropped.
+    fn prune(&mut self, now: i64) -> bool {
+        let cutoff = now - MAX_AGE.as_millis() as i64;
+        let mut dropped = false;
+        for entries in self.index.files.values_mut() {
+            let before = entries.len();
+            entries.retain(|entry| entry.timestamp >= cutoff);
+            let excess = entries.len().saturating_sub(MAX_ENTRIES_PER_FILE);
+            entries.drain(..excess);
+            dropped |= entries.len() != before;
+        }
+        self.index.files.retain(|_, entries| !entries.is_empty());
+
+        // Entries with equal contents share a blob, so it counts once.
+        let mut blobs: HashMap<String, (usize, u64)> = HashMap::new();
+        for entry in self.index.files.values().flatten() {
+            blobs.entry(entry.hash.clone()).or_insert((0, entry.size)).0 += 1;
+        }
+        let mut total: u64 = blobs.values().map(|(_, size)| size).sum();
+        while total > MAX_TOTAL_SIZE {
+            let Some(oldest) = self
+                .index
+                .files
+                .iter()
+                .filter_map(|(key, entries)| entries.first().map(|e| (e.timestamp, key)))
+                .min()
+                .map(|(_, key)| key.clone())
+            else {
+                break;
+            };
+            let entries = self.index.files.get_mut(&oldest).expect("oldest timeline");
+            let removed = entries.remove(0);
+            if entries.is_empty() {
+                self.index.files.remove(&oldest);
+            }
+            if let Some((refs, size)) = blobs.get_mut(&removed.hash) {
+                *refs -= 1;
+                if *refs == 0 {
+                    total -= *size;
+                }
+            }
+            dropped = true;
+        }
+        dropped
+    }
+
+    /// Deletes the blobs no entry refers to any more.
+    fn collect_garbage(&self) {
+        let referenced: HashSet<&str> = self
+            .index
+            .files
+            .values()
+            .flatten()
+            .map(|entry| entry.hash.as_str())
+            .collect();
+        let Ok(dir) = fs::read_dir(self.dir.join(BLOBS_DIR)) else {
+            return;
+        };
+        for entry in dir.flatten() {
+            let name = entry.file_name();
+            if !referenced.contains(name.to_string_lossy().as_ref()) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(BLOBS_DIR).join(hash)
+    }
+}
+
+fn key(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// How a snapshot came about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotSource {
+    /// Saved from the editor.
+    Save,
+    /// Found on disk before a save without being in the history yet: the
+    /// file as it was before its first save, or after an outside change.
+    External,
+    /// Written back by restoring an earlier snapshot.
+    Restore,
+}
+
+/// One point on a file's timeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: i64,
+    pub size: u64,
+    /// SHA-256 of the contents; entries with equal contents share storage.
+    pub hash: String,
+    pub source: SnapshotSource,
+}
+
+/// A snapshot next to the current file, in the shape of `GitDiff` so the
+/// same diff editor can show it.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryDiff {
+    pub path: String,
+    pub entry: HistoryEntry,
+    pub old_content: String,
+    /// Empty when the file no longer exists.
+    pub new_content: String,
+    pub language: String,
+    pub binary: bool,
+}
@@ -0,0 +1,32 @@
+use crate::lsp::types::LspServerCommand;
+
+/// Returns the stdio language server used for a `language_from_path` id when
+/// the frontend does not provide an explicit command.
+pub fn default_server_for(language_id: &str) -> Option<LspServerCommand> {
+    let (program, args): (&str, &[&str]) = match language_id {
+        "rust" => ("rust-analyzer", &[]),
+        "typescript" | "javascript" => ("typescript-language-server", &["--stdio"]),
+        "json" => ("vscode-json-language-server", &["--stdio"]),
+        "css" => ("vscode-css-language-server", &["--stdio"]),
+        "html" => ("vscode-html-language-server", &["--stdio"]),
+        "yaml" => ("yaml-language-server", &["--stdio"]),
+        "toml" => ("taplo", &["lsp", "stdio"]),
+        "markdown" => ("marksman", &["server"]),
+        _ => return None,
+    };
+    Some(LspServerCommand {
+        program: program.to_string(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+    })
+}
+
+/// Programs to try when spawning `program`. npm-installed servers are `.cmd`
+/// shims on Windows, which `Command::new` does not resolve without the
+/// extension.
+pub fn program_candidates(program: &str) -> Vec<String> {
+    let mut candidates = vec![program.to_string()];
+    if cfg!(target_os = "windows") && std::path::Path::new(program).extension().is_none() {
+        candidates.push(format!("{program}.cmd"));
+    }
+    candidates
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+/// Text document as last synchronised with the language server, kept so the
+/// documents can be reopened after a crashed server is restarted.
+#[derive(Clone, Debug)]
+pub struct OpenDocument {
+    pub language_id: String,
+    pub version: i64,
+    pub text: String,
+}
+
+#[derive(Default)]
+pub struct DocumentStore {
+    documents: BTreeMap<String, OpenDocument>,
+}
+
+impl DocumentStore {
+    /// Updates the store from a client notification. Non-document
+    /// notifications are ignored.
+    pub fn track(&mut self, method: &str, params: &Value) {
+        let uri = match params.pointer("/textDocument/uri").and_then(Value::as_str) {
+            Some(uri) => uri.to_string(),
+            None => return,
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                self.documents.insert(
+                    uri,
+                    OpenDocument {
+                        language_id: doc["languageId"].as_str().unwrap_or_default().to_string(),
+                        version: doc["version"].as_i64().unwrap_or_default(),
+                        text: doc["text"].as_str().unwrap_or_default().to_string(),
+                    },
+                );
+            }
+            "textDocument/didChange" => {
+                let Some(document) = self.documents.get_mut(&uri) else {
+                    return;
+                };
+                if let Some(version) = params
+                    .pointer("/textDocument/version")
+                    .and_then(Value::as_i64)
+                {
+                    document.version = version;
+                }
+                let changes = params["contentChanges"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                for change in changes {
+                    apply_change(&mut document.text, &change);
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn uris(&self) -> Vec<String> {
+        self.documents.keys().cloned().collect()
+    }
+
+    /// `textDocument/didOpen` notifications replaying every tracked document.
+    pub fn reopen_notifications(&self) -> Vec<Value> {
+        self.documents
+            .iter()
+            .map(|(uri, doc)| {
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/didOpen",
+                    "params": {
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": doc.language_id,
+                            "version": doc.version,
+                            "text": doc.text,
+                        }
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+fn apply_change(text: &mut String, change: &Value) {
+    let new_text = change["text"].as_str().unwrap_or_default();
+    let range = match change.get("range") {
+        Some(range) if !range.is_null() => range,
+        _ => {
+            *text = new_text.to_string();
+            return;
+        }
+    };
+    let start = offset_at(text, &range["start"]);
+    let end = offset_at(text, &range["end"]).max(start);
+    text.replace_range(start..end, new_text);
+}
+
+/// Converts an LSP position (zero-based line, UTF-16 character) into a byte
+/// offset, clamping positions past the end of a line or of the document.
+fn offset_at(text: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or_default() as usize;
+    let character = position["character"].as_u64().unwrap_or_default() as usize;
+
+    let mut line_start = 0;
+    for _ in 0..line {
+        match text[line_start..].find('\n') {
+            Some(idx) => line_start += idx + 1,
+            None => return text.len(),
+        }
+    }
+
+    let line_text = &text[line_start..];
+    let line_end = line_text.find('\n').unwrap_or(line_text.len());
+    let mut units = 0;
+    for (idx, ch) in line_text[..line_end].char_indices() {
+        if units >= character {
+            return line_start + idx;
+        }
+        units += ch.len_utf16();
+    }
+    line_start + line_end
+}
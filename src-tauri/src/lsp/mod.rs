@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::Path,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use serde_json::Value;
+use tauri::AppHandle;
+
+pub mod config;
+pub mod documents;
+pub mod server;
+pub mod transport;
+pub mod types;
+
+use server::LspServer;
+use types::{LspServerCommand, LspServerInfo, LspServerStatus};
+
+pub type LspResult<T> = Result<T, LspError>;
+
+#[derive(Debug)]
+pub enum LspError {
+    NotFound(u32),
+    NotRunning(u32),
+    Spawn(String),
+    Protocol(String),
+    Io(String),
+    InvalidInput(String),
+}
+
+impl fmt::Display for LspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LspError::NotFound(id) => write!(f, "Language server {id} not found"),
+            LspError::NotRunning(id) => write!(f, "Language server {id} is not running"),
+            LspError::Spawn(msg)
+            | LspError::Protocol(msg)
+            | LspError::Io(msg)
+            | LspError::InvalidInput(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for LspError {
+    fn from(value: std::io::Error) -> Self {
+        LspError::Io(value.to_string())
+    }
+}
+
+/// Language servers keyed by id. One process is shared by every language
+/// that resolves to the same command within the same workspace root.
+#[derive(Clone, Default)]
+pub struct LspState {
+    servers: Arc<Mutex<HashMap<u32, Arc<LspServer>>>>,
+    next_id: Arc<AtomicU32>,
+}
+
+impl LspState {
+    pub fn start(
+        &self,
+        app: &AppHandle,
+        root: &Path,
+        language_id: &str,
+        command: Option<LspServerCommand>,
+    ) -> LspResult<LspServerInfo> {
+        if !root.is_dir() {
+            return Err(LspError::InvalidInput(format!(
+                "Workspace root {} is not a directory",
+                root.display()
+            )));
+        }
+        let command = command
+            .or_else(|| config::default_server_for(language_id))
+            .ok_or_else(|| {
+                LspError::InvalidInput(format!("No language server configured for {language_id}"))
+            })?;
+
+        let mut servers = self.servers.lock().expect("lsp servers poisoned");
+        if let Some(server) = servers
+            .values()
+            .find(|server| server.root == root && server.command == command)
+        {
+            if matches!(
+                server.status(),
+                LspServerStatus::Failed | LspServerStatus::Stopped
+            ) {
+                server.reset_restarts();
+                server.launch(app)?;
+            }
+            return Ok(server.info());
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let server = Arc::new(LspServer::new(
+            id,
+            root.to_path_buf(),
+            language_id.to_string(),
+            command,
+        ));
+        server.launch(app)?;
+        servers.insert(id, Arc::clone(&server));
+        Ok(server.info())
+    }
+
+    pub fn send(&self, id: u32, message: &Value) -> LspResult<()> {
+        self.get(id)?.send(message)
+    }
+
+    /// Gracefully shuts the server down. Blocks for up to the shutdown
+    /// timeout, so callers should run it off the async runtime.
+    pub fn stop(&self, id: u32) -> LspResult<()> {
+        let server = self
+            .servers
+            .lock()
+            .expect("lsp servers poisoned")
+            .remove(&id)
+            .ok_or(LspError::NotFound(id))?;
+        server.shutdown();
+        Ok(())
+    }
+
+    pub fn stop_all(&self) {
+        let servers: Vec<Arc<LspServer>> = self
+            .servers
+            .lock()
+            .expect("lsp servers poisoned")
+            .drain()
+            .map(|(_, server)| server)
+            .collect();
+        let handles: Vec<_> = servers
+            .into_iter()
+            .map(|server| thread::spawn(move || server.shutdown()))
+            .collect();
+        for handle in handles {
+            let _ = handle.join();
+        }
+    }
+
+    pub fn list(&self) -> Vec<LspServerInfo> {
+        let servers = self.servers.lock().expect("lsp servers poisoned");
+        let mut infos: Vec<LspServerInfo> = servers.values().map(|s| s.info()).collect();
+        infos.sort_by_key(|info| info.id);
+        infos
+    }
+
+    fn get(&self, id: u32) -> LspResult<Arc<LspServer>> {
+        self.servers
+            .lock()
+            .expect("lsp servers poisoned")
+            .get(&id)
+            .cloned()
+            .ok_or(LspError::NotFound(id))
+    }
+}
@@ -0,0 +1,362 @@
+use std::{
+    io::{BufRead, BufReader, ErrorKind},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::lsp::{
+    config,
+    documents::DocumentStore,
+    transport,
+    types::{LspLogEvent, LspMessageEvent, LspServerCommand, LspServerInfo, LspServerStatus},
+    LspError, LspResult,
+};
+
+const MAX_RESTARTS: usize = 5;
+const RESTART_WINDOW: Duration = Duration::from_secs(180);
+const RESTART_BACKOFF: Duration = Duration::from_millis(500);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Requests issued by the bridge itself use string ids with these prefixes so
+// their responses are not forwarded to the webview.
+const REPLAY_ID_PREFIX: &str = "nova-replay-";
+const SHUTDOWN_ID: &str = "nova-shutdown";
+
+struct ServerProcess {
+    child: Child,
+    stdin: ChildStdin,
+}
+
+pub struct LspServer {
+    pub id: u32,
+    pub root: PathBuf,
+    pub language_id: String,
+    pub command: LspServerCommand,
+    process: Mutex<Option<ServerProcess>>,
+    documents: Mutex<DocumentStore>,
+    initialize_params: Mutex<Option<Value>>,
+    status: Mutex<LspServerStatus>,
+    stopping: AtomicBool,
+    restarts: Mutex<Vec<Instant>>,
+    restart_count: AtomicU32,
+    generation: AtomicU64,
+}
+
+impl LspServer {
+    pub fn new(id: u32, root: PathBuf, language_id: String, command: LspServerCommand) -> Self {
+        Self {
+            id,
+            root,
+            language_id,
+            command,
+            process: Mutex::new(None),
+            documents: Mutex::new(DocumentStore::default()),
+            initialize_params: Mutex::new(None),
+            status: Mutex::new(LspServerStatus::Starting),
+            stopping: AtomicBool::new(false),
+            restarts: Mutex::new(Vec::new()),
+            restart_count: AtomicU32::new(0),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn status(&self) -> LspServerStatus {
+        *self.status.lock().expect("lsp status poisoned")
+    }
+
+    pub fn info(&self) -> LspServerInfo {
+        LspServerInfo {
+            id: self.id,
+            root: self.root.to_string_lossy().replace('\\', "/"),
+            language_id: self.language_id.clone(),
+            command: self.command.clone(),
+            status: self.status(),
+            restart_count: self.restart_count.load(Ordering::Relaxed),
+            open_documents: self
+                .documents
+                .lock()
+                .expect("lsp documents poisoned")
+                .uris(),
+        }
+    }
+
+    /// Spawns the server process and its reader threads. When the frontend
+    /// already initialised a previous process, the handshake and open
+    /// documents are replayed so the webview does not notice the restart.
+    pub fn launch(self: &Arc<Self>, app: &AppHandle) -> LspResult<()> {
+        self.stopping.store(false, Ordering::SeqCst);
+        let mut child = spawn_process(&self.command, &self.root)?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| LspError::Spawn(format!("{} has no stdin", self.command.program)))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| LspError::Spawn(format!("{} has no stdout", self.command.program)))?;
+        let stderr = child.stderr.take();
+
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        *self.process.lock().expect("lsp process poisoned") = Some(ServerProcess { child, stdin });
+
+        if let Some(stderr) = stderr {
+            let handle = app.clone();
+            let server_id = self.id;
+            thread::spawn(move || {
+                for line in BufReader::new(stderr).lines() {
+                    let Ok(line) = line else { break };
+                    let _ = handle.emit("lsp-log", LspLogEvent { server_id, line });
+                }
+            });
+        }
+
+        let server = Arc::clone(self);
+        let handle = app.clone();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                match transport::read_message(&mut reader) {
+                    Ok(Some(message)) => server.handle_server_message(&handle, message),
+                    Ok(None) => break,
+                    Err(LspError::Protocol(msg)) => server.log(&handle, msg),
+                    Err(_) => break,
+                }
+            }
+            server.on_exit(&handle, generation);
+        });
+
+        let replay = self
+            .initialize_params
+            .lock()
+            .expect("lsp init params poisoned")
+            .clone();
+        if let Some(params) = replay {
+            self.write(&json!({
+                "jsonrpc": "2.0",
+                "id": format!("{REPLAY_ID_PREFIX}{generation}"),
+                "method": "initialize",
+                "params": params,
+            }))?;
+        }
+
+        self.set_status(app, LspServerStatus::Running);
+        Ok(())
+    }
+
+    /// Forwards a JSON-RPC message from the webview, recording the
+    /// handshake and document lifecycle needed to restore a restarted server.
+    pub fn send(&self, message: &Value) -> LspResult<()> {
+        if let Some(method) = message.get("method").and_then(Value::as_str) {
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+            match method {
+                "initialize" => {
+                    *self
+                        .initialize_params
+                        .lock()
+                        .expect("lsp init params poisoned") = Some(params);
+                }
+                "exit" => self.stopping.store(true, Ordering::SeqCst),
+                _ => self
+                    .documents
+                    .lock()
+                    .expect("lsp documents poisoned")
+                    .track(method, &params),
+            }
+        }
+        self.write(message)
+    }
+
+    pub fn shutdown(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        let _ = self.write(&json!({ "jsonrpc": "2.0", "id": SHUTDOWN_ID, "method": "shutdown" }));
+        let _ = self.write(&json!({ "jsonrpc": "2.0", "method": "exit" }));
+
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        loop {
+            {
+                let mut guard = self.process.lock().expect("lsp process poisoned");
+                let Some(process) = guard.as_mut() else {
+                    break;
+                };
+                match process.child.try_wait() {
+                    Ok(Some(_)) => break,
+                    Ok(None) if Instant::now() < deadline => {}
+                    _ => {
+                        let _ = process.child.kill();
+                        break;
+                    }
+                }
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    fn write(&self, message: &Value) -> LspResult<()> {
+        let mut guard = self.process.lock().expect("lsp process poisoned");
+        let process = guard.as_mut().ok_or(LspError::NotRunning(self.id))?;
+        transport::write_message(&mut process.stdin, message)
+    }
+
+    fn handle_server_message(&self, app: &AppHandle, message: Value) {
+        let internal_id = message
+            .get("id")
+            .and_then(Value::as_str)
+            .filter(|id| id.starts_with(REPLAY_ID_PREFIX) || *id == SHUTDOWN_ID);
+        let is_response = message.get("method").is_none();
+
+        match internal_id {
+            Some(id) if is_response && id.starts_with(REPLAY_ID_PREFIX) => {
+                let _ =
+                    self.write(&json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }));
+                let reopen = self
+                    .documents
+                    .lock()
+                    .expect("lsp documents poisoned")
+                    .reopen_notifications();
+                for notification in reopen {
+                    let _ = self.write(&notification);
+                }
+            }
+            Some(_) if is_response => {}
+            _ => {
+                let _ = app.emit(
+                    "lsp-message",
+                    LspMessageEvent {
+                        server_id: self.id,
+                        message,
+                    },
+                );
+            }
+        }
+    }
+
+    fn on_exit(self: &Arc<Self>, app: &AppHandle, generation: u64) {
+        // A newer process already replaced the one this reader belonged to.
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+
+        let exit_status = self
+            .process
+            .lock()
+            .expect("lsp process poisoned")
+            .take()
+            .and_then(|mut process| process.child.wait().ok());
+
+        if self.stopping.load(Ordering::SeqCst) {
+            self.set_status(app, LspServerStatus::Stopped);
+            return;
+        }
+
+        let exit_label = exit_status
+            .map(|status| status.to_string())
+            .unwrap_or_else(|| "unknown status".to_string());
+        let attempt = match self.register_restart() {
+            Some(attempt) => attempt,
+            None => {
+                self.log(
+                    app,
+                    format!(
+                        "{} exited ({exit_label}); restart limit reached",
+                        self.command.program
+                    ),
+                );
+                self.set_status(app, LspServerStatus::Failed);
+                return;
+            }
+        };
+
+        self.log(
+            app,
+            format!("{} exited ({exit_label}); restarting", self.command.program),
+        );
+        self.set_status(app, LspServerStatus::Restarting);
+        thread::sleep(RESTART_BACKOFF * attempt as u32);
+
+        if self.stopping.load(Ordering::SeqCst) {
+            self.set_status(app, LspServerStatus::Stopped);
+            return;
+        }
+        if let Err(err) = self.launch(app) {
+            self.log(
+                app,
+                format!("Failed to restart {}: {err}", self.command.program),
+            );
+            self.set_status(app, LspServerStatus::Failed);
+        }
+    }
+
+    /// Records a restart attempt and returns how many happened within
+    /// `RESTART_WINDOW`, or `None` once the limit is exceeded.
+    fn register_restart(&self) -> Option<usize> {
+        let now = Instant::now();
+        let mut restarts = self.restarts.lock().expect("lsp restarts poisoned");
+        restarts.retain(|at| now.duration_since(*at) <= RESTART_WINDOW);
+        if restarts.len() >= MAX_RESTARTS {
+            return None;
+        }
+        restarts.push(now);
+        self.restart_count.fetch_add(1, Ordering::Relaxed);
+        Some(restarts.len())
+    }
+
+    pub fn reset_restarts(&self) {
+        self.restarts.lock().expect("lsp restarts poisoned").clear();
+    }
+
+    fn set_status(&self, app: &AppHandle, status: LspServerStatus) {
+        *self.status.lock().expect("lsp status poisoned") = status;
+        let _ = app.emit("lsp-status", self.info());
+    }
+
+    fn log(&self, app: &AppHandle, line: String) {
+        let _ = app.emit(
+            "lsp-log",
+            LspLogEvent {
+                server_id: self.id,
+                line,
+            },
+        );
+    }
+}
+
+fn spawn_process(command: &LspServerCommand, root: &Path) -> LspResult<Child> {
+    let mut last_error = None;
+    for program in config::program_candidates(&command.program) {
+        let mut cmd = Command::new(&program);
+        cmd.args(&command.args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        match cmd.spawn() {
+            Ok(child) => return Ok(child),
+            Err(err) if err.kind() == ErrorKind::NotFound => last_error = Some(err),
+            Err(err) => return Err(LspError::Spawn(format!("Failed to start {program}: {err}"))),
+        }
+    }
+    Err(LspError::Spawn(format!(
+        "Failed to start {}: {}",
+        command.program,
+        last_error
+            .map(|err| err.to_string())
+            .unwrap_or_else(|| "program not found".to_string())
+    )))
+}
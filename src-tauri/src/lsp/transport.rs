@@ -0,0 +1,44 @@
+use std::io::{BufRead, Write};
+
+use serde_json::Value;
+
+use crate::lsp::{LspError, LspResult};
+
+/// Reads one `Content-Length` framed JSON-RPC message. Returns `Ok(None)` when
+/// the server closed its stdout.
+pub fn read_message<R: BufRead>(reader: &mut R) -> LspResult<Option<Value>> {
+    let mut content_length: Option<usize> = None;
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let line = header.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let length = content_length
+        .ok_or_else(|| LspError::Protocol("Missing Content-Length header".to_string()))?;
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| LspError::Protocol(format!("Invalid JSON-RPC payload: {e}")))
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> LspResult<()> {
+    let body = serde_json::to_vec(message)
+        .map_err(|e| LspError::Protocol(format!("Failed to serialize message: {e}")))?;
+    write!(writer, "Content-Length: {}\r\n\r\n", body.len())?;
+    writer.write_all(&body)?;
+    writer.flush()?;
+    Ok(())
+}
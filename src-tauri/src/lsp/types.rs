@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LspServerCommand {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StartLspRequest {
+    pub root: String,
+    pub language_id: String,
+    #[serde(default)]
+    pub command: Option<LspServerCommand>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LspServerStatus {
+    Starting,
+    Running,
+    Restarting,
+    Stopped,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LspServerInfo {
+    pub id: u32,
+    pub root: String,
+    pub language_id: String,
+    pub command: LspServerCommand,
+    pub status: LspServerStatus,
+    pub restart_count: u32,
+    pub open_documents: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LspMessageEvent {
+    pub server_id: u32,
+    pub message: serde_json::Value,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LspLogEvent {
+    pub server_id: u32,
+    pub line: String,
+}
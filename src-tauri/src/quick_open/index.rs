@@ -0,0 +1,140 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use crate::search::walker;
+
+/// Flat list of the files below a workspace root, honouring the same ignore
+/// rules as project search. Directories are tracked as well so watcher
+/// events can be checked against them: a path is only indexed when its
+/// parent directory was, which keeps ignored trees such as `target/` out.
+pub struct FileIndex {
+    root: PathBuf,
+    files: BTreeSet<String>,
+    dirs: BTreeSet<String>,
+}
+
+impl FileIndex {
+    pub fn build(root: &Path) -> Result<Self, String> {
+        let mut index = Self {
+            root: root.to_path_buf(),
+            files: BTreeSet::new(),
+            dirs: BTreeSet::new(),
+        };
+        index.dirs.insert(String::new());
+        index.add_tree(root)?;
+        Ok(index)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(String::as_str)
+    }
+
+    pub fn contains(&self, relative: &str) -> bool {
+        self.files.contains(relative)
+    }
+
+    /// Brings the index in line with the current state of `paths`, whatever
+    /// happened to them (created, removed or renamed). Returns `true` when an
+    /// ignore file changed and the index has to be rebuilt instead.
+    pub fn apply(&mut self, paths: &[PathBuf]) -> bool {
+        // Paths to add back, by parent, so each directory is listed once.
+        let mut added: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+        for path in paths {
+            let Some(relative) = self.relative(path) else {
+                continue;
+            };
+            if is_ignore_file(path) {
+                return true;
+            }
+
+            self.remove(&relative);
+            if path.exists() && self.dirs.contains(parent_of(&relative)) {
+                let parent = path.parent().unwrap_or(&self.root).to_path_buf();
+                added.entry(parent).or_default().insert(path.clone());
+            }
+        }
+        for (parent, paths) in added {
+            let _ = self.add_entries(&parent, &paths);
+        }
+        false
+    }
+
+    /// Walks `dir` and indexes everything below it.
+    fn add_tree(&mut self, dir: &Path) -> Result<(), String> {
+        let builder = walker::build_walker(dir, &[], &[])?;
+        let no_cancel = AtomicBool::new(false);
+        for path in walker::collect_entries(&builder, &no_cancel) {
+            self.insert(&path.path, path.is_dir);
+        }
+        Ok(())
+    }
+
+    /// Indexes new `paths` directly inside `parent`, unless ignore rules
+    /// exclude them. The parent is listed once for all of them.
+    fn add_entries(&mut self, parent: &Path, paths: &BTreeSet<PathBuf>) -> Result<(), String> {
+        let mut builder = walker::build_walker(parent, &[], &[])?;
+        builder.max_depth(Some(1));
+        let no_cancel = AtomicBool::new(false);
+        for entry in walker::collect_entries(&builder, &no_cancel) {
+            if !paths.contains(&entry.path) {
+                continue;
+            }
+            if entry.is_dir {
+                self.add_tree(&entry.path)?;
+            } else {
+                self.insert(&entry.path, false);
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, path: &Path, is_dir: bool) {
+        let Some(relative) = self.relative(path) else {
+            return;
+        };
+        if is_dir {
+            self.dirs.insert(relative);
+        } else if !relative.is_empty() {
+            self.files.insert(relative);
+        }
+    }
+
+    /// Drops `relative` and, if it was a directory, everything below it.
+    fn remove(&mut self, relative: &str) {
+        self.files.remove(relative);
+        if self.dirs.remove(relative) {
+            let prefix = format!("{relative}/");
+            self.files.retain(|f| !f.starts_with(&prefix));
+            self.dirs.retain(|d| !d.starts_with(&prefix));
+        }
+    }
+
+    /// `path` relative to the root, `/`-separated; `None` outside the root.
+    pub fn relative(&self, path: &Path) -> Option<String> {
+        path.strip_prefix(&self.root)
+            .ok()
+            .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+fn parent_of(relative: &str) -> &str {
+    relative.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
+
+pub fn is_ignore_file(path: &Path) -> bool {
+    matches!(
+        path.file_name().and_then(|n| n.to_str()),
+        Some(".gitignore" | ".ignore")
+    )
+}
@@ -0,0 +1,176 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use nucleo_matcher::{
+    pattern::{CaseMatching, Normalization, Pattern},
+    Config, Matcher, Utf32Str,
+};
+
+pub mod index;
+pub mod types;
+
+use crate::watcher::types::FileChangeBatch;
+use index::FileIndex;
+use types::{QuickOpenIndexInfo, QuickOpenMatch};
+
+pub const DEFAULT_LIMIT: usize = 100;
+
+/// Cached file indexes for quick open, keyed by workspace root. Indexes are
+/// built on first use and then kept current from file watcher events.
+#[derive(Clone, Default)]
+pub struct QuickOpenState {
+    indexes: Arc<Mutex<HashMap<PathBuf, FileIndex>>>,
+}
+
+impl QuickOpenState {
+    /// Builds the index for `root` unless it is already cached.
+    pub fn ensure_index(&self, root: &Path) -> Result<QuickOpenIndexInfo, String> {
+        if let Some(index) = self
+            .indexes
+            .lock()
+            .expect("quick open indexes poisoned")
+            .get(root)
+        {
+            return Ok(info(index));
+        }
+        self.rebuild(root)
+    }
+
+    /// Discards the cached index for `root` and walks the tree again.
+    pub fn rebuild(&self, root: &Path) -> Result<QuickOpenIndexInfo, String> {
+        let index = FileIndex::build(root)?;
+        let info = info(&index);
+        self.indexes
+            .lock()
+            .expect("quick open indexes poisoned")
+            .insert(root.to_path_buf(), index);
+        Ok(info)
+    }
+
+    /// Drops the indexes of roots no longer in the workspace.
+    pub fn retain_roots(&self, roots: &[PathBuf]) {
+        self.indexes
+            .lock()
+            .expect("quick open indexes poisoned")
+            .retain(|root, _| roots.contains(root));
+    }
+
+    /// Returns the best fuzzy matches for `query`, highest score first. An
+    /// empty query lists files in path order.
+    pub fn query(
+        &self,
+        root: &Path,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<QuickOpenMatch>, String> {
+        self.ensure_index(root)?;
+        let indexes = self.indexes.lock().expect("quick open indexes poisoned");
+        let Some(index) = indexes.get(root) else {
+            return Ok(Vec::new());
+        };
+
+        if query.trim().is_empty() {
+            return Ok(index
+                .files()
+                .take(limit)
+                .map(|path| to_match(path, 0, &[]))
+                .collect());
+        }
+
+        let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+        let mut matcher = Matcher::new(Config::DEFAULT.match_paths());
+        let mut buf = Vec::new();
+        let mut scored: Vec<(u32, &str)> = index
+            .files()
+            .filter_map(|path| {
+                pattern
+                    .score(Utf32Str::new(path, &mut buf), &mut matcher)
+                    .map(|score| (score, path))
+            })
+            .collect();
+        // Higher scores first; among equals, shorter paths are the likelier pick.
+        scored.sort_by_key(|(score, path)| (Reverse(*score), path.len(), *path));
+        scored.truncate(limit);
+
+        let mut indices = Vec::new();
+        Ok(scored
+            .into_iter()
+            .map(|(score, path)| {
+                indices.clear();
+                pattern.indices(Utf32Str::new(path, &mut buf), &mut matcher, &mut indices);
+                indices.sort_unstable();
+                indices.dedup();
+                to_match(path, score, &indices)
+            })
+            .collect())
+    }
+
+    /// Applies a file watcher batch to every index it concerns. Content
+    /// changes are ignored, except for ignore files, which may hide or reveal
+    /// whole trees so the affected index is rebuilt.
+    pub fn apply_changes(&self, batch: &FileChangeBatch) {
+        let structural = batch.structural_paths();
+        let changed = batch.changed_paths();
+
+        let mut stale = Vec::new();
+        {
+            let mut indexes = self.indexes.lock().expect("quick open indexes poisoned");
+            for index in indexes.values_mut() {
+                // A changed path the index lacks was replaced by a rename.
+                let paths: Vec<PathBuf> = structural
+                    .iter()
+                    .chain(changed.iter().filter(|path| {
+                        index::is_ignore_file(path)
+                            || index
+                                .relative(path)
+                                .is_some_and(|relative| !index.contains(&relative))
+                    }))
+                    .filter(|path| path.starts_with(index.root()))
+                    .cloned()
+                    .collect();
+                if !paths.is_empty() && index.apply(&paths) {
+                    stale.push(index.root().to_path_buf());
+                }
+            }
+        }
+
+        for root in stale {
+            let _ = self.rebuild(&root);
+        }
+    }
+}
+
+fn info(index: &FileIndex) -> QuickOpenIndexInfo {
+    QuickOpenIndexInfo {
+        root: index.root().to_string_lossy().replace('\\', "/"),
+        file_count: index.len(),
+    }
+}
+
+fn to_match(path: &str, score: u32, char_indices: &[u32]) -> QuickOpenMatch {
+    QuickOpenMatch {
+        path: path.to_string(),
+        name: path.rsplit('/').next().unwrap_or(path).to_string(),
+        score,
+        positions: utf16_positions(path, char_indices),
+    }
+}
+
+/// Converts sorted char indices into `text` into UTF-16 offsets.
+pub fn utf16_positions(text: &str, char_indices: &[u32]) -> Vec<u32> {
+    let mut positions = Vec::with_capacity(char_indices.len());
+    let mut wanted = char_indices.iter().peekable();
+    let mut utf16 = 0;
+    for (idx, ch) in text.chars().enumerate() {
+        if wanted.peek().is_some_and(|&&w| w as usize == idx) {
+            positions.push(utf16);
+            wanted.next();
+        }
+        utf16 += ch.len_utf16() as u32;
+    }
+    positions
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuickOpenRequest {
+    pub root: String,
+    pub query: String,
+    /// Maximum number of matches returned. Defaults to `DEFAULT_LIMIT`.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// One ranked quick-open candidate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickOpenMatch {
+    /// Path relative to the workspace root, `/`-separated.
+    pub path: String,
+    pub name: String,
+    pub score: u32,
+    /// Matched positions in `path`, as UTF-16 offsets for direct use in JS.
+    pub positions: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuickOpenIndexInfo {
+    pub root: String,
+    pub file_count: usize,
+}
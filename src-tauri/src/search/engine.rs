@@ -0,0 +1,316 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder};
+
+use crate::search::{
+    types::{HitRange, SearchHit},
+    walker, SearchContext,
+};
+
+/// Files scanned in parallel before their results are emitted. Keeping
+/// batches bounded lets results stream out in path order while every core
+/// stays busy.
+const BATCH_SIZE: usize = 256;
+/// Upper bound for the context lines requested before and after each hit.
+const MAX_CONTEXT_LINES: usize = 20;
+
+/// Context lines to attach to each hit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContextLines {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl ContextLines {
+    pub fn new(before: usize, after: usize) -> Self {
+        Self {
+            before: before.min(MAX_CONTEXT_LINES),
+            after: after.min(MAX_CONTEXT_LINES),
+        }
+    }
+}
+
+/// Line matcher shared by all scanning threads.
+pub struct LineMatcher {
+    regex: Option<Regex>,
+    literal: String,
+}
+
+impl LineMatcher {
+    pub fn new(query: &str, use_regex: bool, case_sensitive: bool) -> Result<Self, String> {
+        // Case-insensitive literals go through the regex engine too: it folds
+        // case without lowercasing the line, so offsets stay valid for it.
+        let regex = if use_regex || !case_sensitive {
+            let pattern = if use_regex {
+                query.to_string()
+            } else {
+                regex::escape(query)
+            };
+            let regex = RegexBuilder::new(&pattern)
+                .case_insensitive(!case_sensitive)
+                .build()
+                .map_err(|e| format!("Invalid regex: {}", e))?;
+            Some(regex)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            regex,
+            literal: query.to_string(),
+        })
+    }
+
+    /// Returns the `(start, end)` byte offsets of every match in `line`.
+    pub fn find_all(&self, line: &str) -> Vec<(usize, usize)> {
+        match self.regex {
+            Some(ref re) => re
+                .find_iter(line)
+                .map(|mat| (mat.start(), mat.end()))
+                .collect(),
+            None => line
+                .match_indices(&self.literal)
+                .map(|(pos, text)| (pos, pos + text.len()))
+                .collect(),
+        }
+    }
+}
+
+/// Matcher for multiline mode. The query is matched against the whole file,
+/// with `^`/`$` anchoring at line boundaries.
+pub struct MultilineMatcher {
+    regex: Regex,
+}
+
+impl MultilineMatcher {
+    pub fn new(query: &str, use_regex: bool, case_sensitive: bool) -> Result<Self, String> {
+        let pattern = if use_regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!case_sensitive)
+            .multi_line(true)
+            .crlf(true)
+            .build()
+            .map_err(|e| format!("Invalid regex: {}", e))?;
+        Ok(Self { regex })
+    }
+}
+
+/// Searches one file line by line. Binary files are detected by content and
+/// skipped, as are files that turn out not to be UTF-8.
+pub fn scan_file(
+    ctx: &SearchContext,
+    root: &Path,
+    path: &Path,
+    matcher: &LineMatcher,
+    context: ContextLines,
+) -> Vec<SearchHit> {
+    let mut hits: Vec<SearchHit> = Vec::new();
+
+    let file = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return hits,
+    };
+
+    let mut reader = BufReader::with_capacity(walker::BINARY_SNIFF_LEN, file);
+
+    // Skip binary files by content rather than by extension
+    match reader.fill_buf() {
+        Ok(prefix) if walker::looks_binary(prefix) => return hits,
+        Ok(_) => {}
+        Err(_) => return hits,
+    }
+
+    let relative_path = relative_path(root, path);
+
+    // Lines preceding the current one, and the hits still collecting
+    // trailing context as (index into `hits`, lines missing).
+    let mut previous: VecDeque<String> = VecDeque::with_capacity(context.before);
+    let mut pending: Vec<(usize, usize)> = Vec::new();
+
+    // Search line by line
+    for (line_num, line_result) in reader.lines().enumerate() {
+        if ctx.is_cancelled() {
+            break;
+        }
+
+        let line = match line_result {
+            Ok(l) => l,
+            Err(_) => break, // Skip non-UTF8 files
+        };
+
+        pending.retain_mut(|(idx, missing)| {
+            hits[*idx].context_after.push(line.clone());
+            *missing -= 1;
+            *missing > 0
+        });
+
+        for (start, end) in matcher.find_all(&line) {
+            let start_pos = Position::in_line(&line, start);
+            let end_pos = Position::in_line(&line, end);
+
+            if context.after > 0 {
+                pending.push((hits.len(), context.after));
+            }
+            hits.push(SearchHit {
+                file: relative_path.clone(),
+                line: (line_num + 1) as u32,
+                column: start_pos.utf16 + 1,
+                end_line: (line_num + 1) as u32,
+                end_column: end_pos.utf16 + 1,
+                byte_range: HitRange::new(start_pos.byte, end_pos.byte),
+                char_range: HitRange::new(start_pos.chars, end_pos.chars),
+                utf16_range: HitRange::new(start_pos.utf16, end_pos.utf16),
+                match_text: line[start..end].to_string(),
+                line_text: line.clone(),
+                context_before: previous.iter().cloned().collect(),
+                context_after: Vec::new(),
+            });
+        }
+
+        if context.before > 0 {
+            if previous.len() == context.before {
+                previous.pop_front();
+            }
+            previous.push_back(line);
+        }
+    }
+
+    hits
+}
+
+/// Searches one file as a whole so matches may span lines.
+pub fn scan_file_multiline(
+    ctx: &SearchContext,
+    root: &Path,
+    path: &Path,
+    matcher: &MultilineMatcher,
+    context: ContextLines,
+) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+
+    let Ok(bytes) = fs::read(path) else {
+        return hits;
+    };
+    if walker::looks_binary(&bytes) {
+        return hits;
+    }
+    let Ok(content) = String::from_utf8(bytes) else {
+        return hits;
+    };
+
+    let relative_path = relative_path(root, path);
+    let lines: Vec<&str> = content
+        .split('\n')
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .collect();
+    // A trailing newline ends the last line rather than starting a new one.
+    let line_count = lines.len() - usize::from(content.ends_with('\n'));
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    // Zero-based line of a byte offset, and the position within that line.
+    let position = |offset: usize| {
+        let line = line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_text = &content[line_starts[line]..];
+        (
+            line,
+            Position::in_line(line_text, offset - line_starts[line]),
+        )
+    };
+
+    for mat in matcher.regex.find_iter(&content) {
+        if ctx.is_cancelled() {
+            break;
+        }
+
+        let (start_line, start_pos) = position(mat.start());
+        if start_line >= line_count {
+            break;
+        }
+        let (end_line, end_pos) = position(mat.end());
+        let before_from = start_line.saturating_sub(context.before);
+        let after_to = (end_line + 1 + context.after).min(line_count);
+
+        hits.push(SearchHit {
+            file: relative_path.clone(),
+            line: (start_line + 1) as u32,
+            column: start_pos.utf16 + 1,
+            end_line: (end_line + 1) as u32,
+            end_column: end_pos.utf16 + 1,
+            byte_range: HitRange::new(start_pos.byte, end_pos.byte),
+            char_range: HitRange::new(start_pos.chars, end_pos.chars),
+            utf16_range: HitRange::new(start_pos.utf16, end_pos.utf16),
+            match_text: mat.as_str().to_string(),
+            line_text: lines[start_line].to_string(),
+            context_before: to_owned_lines(&lines[before_from..start_line]),
+            context_after: to_owned_lines(&lines[(end_line + 1).min(after_to)..after_to]),
+        });
+    }
+
+    hits
+}
+
+/// Offset of a position within its line, counted in bytes, chars and UTF-16
+/// code units.
+struct Position {
+    byte: u32,
+    chars: u32,
+    utf16: u32,
+}
+
+impl Position {
+    fn in_line(line: &str, byte: usize) -> Self {
+        let prefix = &line[..byte];
+        Self {
+            byte: byte as u32,
+            chars: prefix.chars().count() as u32,
+            utf16: prefix.encode_utf16().count() as u32,
+        }
+    }
+}
+
+fn to_owned_lines(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|l| l.to_string()).collect()
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Runs `scan` over `files` on the rayon pool, `BATCH_SIZE` files at a
+/// time, and hands each batch's results to `consume` in file order. A
+/// `search-progress` event follows every batch. Stops when the search is
+/// cancelled or `consume` returns `false`.
+pub fn scan_in_batches<T, S, C>(ctx: &SearchContext, files: &[PathBuf], scan: S, mut consume: C)
+where
+    T: Send,
+    S: Fn(&Path) -> T + Sync,
+    C: FnMut(Vec<T>) -> bool,
+{
+    let total = files.len();
+    let mut scanned = 0;
+    for batch in files.chunks(BATCH_SIZE) {
+        if ctx.is_cancelled() {
+            return;
+        }
+        let results: Vec<T> = batch.par_iter().map(|path| scan(path)).collect();
+        scanned += batch.len();
+        ctx.emit_progress(scanned, total);
+        if !consume(results) {
+            return;
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tauri::{AppHandle, Emitter};
+
+pub mod engine;
+pub mod replace;
+pub mod types;
+pub mod walker;
+
+use types::{
+    FileReplacePreview, ReplacePreviewEvent, SearchCancelledEvent, SearchCompleteEvent,
+    SearchErrorEvent, SearchFilesRequest, SearchHit, SearchHitsEvent, SearchProgressEvent,
+};
+
+const MAX_RESULTS: usize = 1000;
+/// Maximum number of hits carried by one `search-hits` event.
+const HITS_PER_EVENT: usize = 200;
+
+/// Cancellation tokens of the searches currently running, keyed by search id.
+#[derive(Clone, Default)]
+pub struct SearchState {
+    active: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl SearchState {
+    /// Registers a new search. Re-using the id of a running search cancels
+    /// that search first, so a panel can simply restart its own query.
+    pub fn begin(&self, requested_id: Option<String>) -> (String, Arc<AtomicBool>) {
+        let search_id = requested_id.unwrap_or_else(|| {
+            format!(
+                "search-{}",
+                self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+            )
+        });
+        let token = Arc::new(AtomicBool::new(false));
+        let previous = self
+            .active
+            .lock()
+            .expect("search state poisoned")
+            .insert(search_id.clone(), Arc::clone(&token));
+        if let Some(previous) = previous {
+            previous.store(true, Ordering::Relaxed);
+        }
+        (search_id, token)
+    }
+
+    pub fn cancel(&self, search_id: &str) {
+        if let Some(token) = self
+            .active
+            .lock()
+            .expect("search state poisoned")
+            .get(search_id)
+        {
+            token.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn cancel_all(&self) {
+        for token in self.active.lock().expect("search state poisoned").values() {
+            token.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Drops the token once its search ended, unless the id was already
+    /// taken over by a newer search.
+    pub fn finish(&self, search_id: &str, token: &Arc<AtomicBool>) {
+        let mut active = self.active.lock().expect("search state poisoned");
+        if active
+            .get(search_id)
+            .is_some_and(|current| Arc::ptr_eq(current, token))
+        {
+            active.remove(search_id);
+        }
+    }
+}
+
+/// Emits the events of one search, tagged with its id.
+pub struct SearchContext {
+    app: AppHandle,
+    search_id: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SearchContext {
+    pub fn new(app: AppHandle, search_id: String, cancelled: Arc<AtomicBool>) -> Self {
+        Self {
+            app,
+            search_id,
+            cancelled,
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn emit_hits(&self, hits: Vec<SearchHit>) {
+        for chunk in hits.chunks(HITS_PER_EVENT) {
+            let _ = self.app.emit(
+                "search-hits",
+                SearchHitsEvent {
+                    search_id: self.search_id.clone(),
+                    hits: chunk.to_vec(),
+                },
+            );
+        }
+    }
+
+    pub fn emit_preview(&self, preview: FileReplacePreview) {
+        let _ = self.app.emit(
+            "search-replace-preview",
+            ReplacePreviewEvent {
+                search_id: self.search_id.clone(),
+                preview,
+            },
+        );
+    }
+
+    pub fn emit_progress(&self, files_scanned: usize, files_total: usize) {
+        let _ = self.app.emit(
+            "search-progress",
+            SearchProgressEvent {
+                search_id: self.search_id.clone(),
+                files_scanned,
+                files_total,
+            },
+        );
+    }
+
+    pub fn emit_complete(&self, result_count: usize) {
+        let _ = self.app.emit(
+            "search-complete",
+            SearchCompleteEvent {
+                search_id: self.search_id.clone(),
+                result_count,
+            },
+        );
+    }
+
+    pub fn emit_cancelled(&self) {
+        let _ = self.app.emit(
+            "search-cancelled",
+            SearchCancelledEvent {
+                search_id: self.search_id.clone(),
+            },
+        );
+    }
+
+    pub fn emit_error(&self, message: String) {
+        let _ = self.app.emit(
+            "search-error",
+            SearchErrorEvent {
+                search_id: self.search_id.clone(),
+                message,
+            },
+        );
+    }
+}
+
+enum Matcher {
+    Line(engine::LineMatcher),
+    Multiline(engine::MultilineMatcher),
+}
+
+pub fn perform_search(
+    ctx: &SearchContext,
+    root: PathBuf,
+    request: SearchFilesRequest,
+) -> Result<(), String> {
+    if let Some(replace) = request.replace.clone() {
+        return replace::perform_replace_preview(ctx, root, request, replace);
+    }
+
+    let context = engine::ContextLines::new(request.context_before, request.context_after);
+    let matcher = if request.multiline {
+        engine::MultilineMatcher::new(&request.query, request.use_regex, request.case_sensitive)
+            .map(Matcher::Multiline)
+    } else {
+        engine::LineMatcher::new(&request.query, request.use_regex, request.case_sensitive)
+            .map(Matcher::Line)
+    };
+    let matcher = match matcher {
+        Ok(matcher) => matcher,
+        Err(e) => {
+            ctx.emit_error(e.clone());
+            return Err(e);
+        }
+    };
+
+    let files = match collect_files(ctx, &root, &request) {
+        Ok(files) => files,
+        Err(e) => {
+            ctx.emit_error(e.clone());
+            return Err(e);
+        }
+    };
+
+    let mut result_count = 0;
+    engine::scan_in_batches(
+        ctx,
+        &files,
+        |path| match &matcher {
+            Matcher::Line(m) => engine::scan_file(ctx, &root, path, m, context),
+            Matcher::Multiline(m) => engine::scan_file_multiline(ctx, &root, path, m, context),
+        },
+        |batch| {
+            let mut hits: Vec<SearchHit> = batch.into_iter().flatten().collect();
+            hits.truncate(MAX_RESULTS - result_count);
+            result_count += hits.len();
+            ctx.emit_hits(hits);
+            result_count < MAX_RESULTS
+        },
+    );
+
+    if ctx.is_cancelled() {
+        ctx.emit_cancelled();
+    } else {
+        ctx.emit_complete(result_count);
+    }
+    Ok(())
+}
+
+/// Lists the files to search, in deterministic order.
+fn collect_files(
+    ctx: &SearchContext,
+    root: &Path,
+    request: &SearchFilesRequest,
+) -> Result<Vec<PathBuf>, String> {
+    let builder = walker::build_walker(root, &request.include, &request.exclude)?;
+    Ok(walker::collect_files(&builder, &ctx.cancelled))
+}
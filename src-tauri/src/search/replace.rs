@@ -0,0 +1,482 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use regex::{Captures, Regex};
+
+use crate::{
+    files::{atomic::write_atomic, version::content_hash},
+    search::{
+        collect_files, engine,
+        types::{
+            ApplyReplaceRequest, FileReplacePreview, ReplacePreviewLine, ReplaceSummary,
+            SearchFilesRequest,
+        },
+        walker, SearchContext, MAX_RESULTS,
+    },
+    workspace::{policy::Access, WorkspaceState},
+};
+
+/// Applies one replacement pattern line by line. Regex mode expands capture
+/// groups (`$1`, `${name}`); literal mode inserts the replacement verbatim.
+pub struct Replacer<'a> {
+    regex: Regex,
+    replace: &'a str,
+    expand: bool,
+    preserve_case: bool,
+}
+
+impl<'a> Replacer<'a> {
+    pub fn new(
+        query: &str,
+        replace: &'a str,
+        use_regex: bool,
+        case_sensitive: bool,
+        preserve_case: bool,
+    ) -> Result<Self, String> {
+        let pattern = if use_regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        let pattern = if case_sensitive {
+            pattern
+        } else {
+            format!("(?i){pattern}")
+        };
+        let regex = Regex::new(&pattern).map_err(|e| format!("Invalid regex: {e}"))?;
+        Ok(Self {
+            regex,
+            replace,
+            expand: use_regex,
+            preserve_case,
+        })
+    }
+
+    /// Returns the rewritten line and the number of replacements made.
+    pub fn replace_line(&self, line: &str) -> (String, u32) {
+        let mut count = 0;
+        let replaced = self.regex.replace_all(line, |caps: &Captures| {
+            count += 1;
+            let mut dst = String::new();
+            if self.expand {
+                caps.expand(self.replace, &mut dst);
+            } else {
+                dst.push_str(self.replace);
+            }
+            if self.preserve_case {
+                match_case(&caps[0], &dst)
+            } else {
+                dst
+            }
+        });
+        (replaced.into_owned(), count)
+    }
+}
+
+pub fn perform_replace_preview(
+    ctx: &SearchContext,
+    root: PathBuf,
+    request: SearchFilesRequest,
+    replace: String,
+) -> Result<(), String> {
+    let replacer = match Replacer::new(
+        &request.query,
+        &replace,
+        request.use_regex,
+        request.case_sensitive,
+        request.preserve_case,
+    ) {
+        Ok(replacer) => replacer,
+        Err(e) => {
+            ctx.emit_error(e.clone());
+            return Err(e);
+        }
+    };
+
+    let files = match collect_files(ctx, &root, &request) {
+        Ok(files) => files,
+        Err(e) => {
+            ctx.emit_error(e.clone());
+            return Err(e);
+        }
+    };
+
+    let mut result_count = 0;
+    engine::scan_in_batches(
+        ctx,
+        &files,
+        |path| preview_file(&root, path, &replacer),
+        |batch| {
+            for preview in batch.into_iter().flatten() {
+                result_count += preview.lines.len();
+                ctx.emit_preview(preview);
+            }
+            result_count < MAX_RESULTS
+        },
+    );
+
+    if ctx.is_cancelled() {
+        ctx.emit_cancelled();
+        return Ok(());
+    }
+    ctx.emit_complete(result_count);
+    Ok(())
+}
+
+fn preview_file(root: &Path, path: &Path, replacer: &Replacer) -> Option<FileReplacePreview> {
+    let (bytes, content) = read_text_file(path)?;
+
+    let mut lines = Vec::new();
+    for (idx, piece) in content.split_inclusive('\n').enumerate() {
+        let (body, _) = split_line_ending(piece);
+        let (replaced, match_count) = replacer.replace_line(body);
+        if match_count == 0 {
+            continue;
+        }
+        lines.push(ReplacePreviewLine {
+            line: (idx + 1) as u32,
+            original: body.to_string(),
+            replaced,
+            match_count,
+        });
+    }
+    if lines.is_empty() {
+        return None;
+    }
+
+    Some(FileReplacePreview {
+        file: relative_path(root, path),
+        content_hash: content_hash(&bytes),
+        lines,
+    })
+}
+
+/// Applies the selected replacements. Every file is checked against the
+/// hash from its preview first, and nothing is written if any of them
+/// changed on disk. Each file is replaced with `write_atomic`, so it keeps
+/// its mode and owner and a symlink stays a symlink; if a write fails, the
+/// files already replaced get their previous contents back the same way.
+pub fn apply_replacements(
+    workspace: &WorkspaceState,
+    root: &Path,
+    request: &ApplyReplaceRequest,
+) -> Result<ReplaceSummary, String> {
+    let replacer = Replacer::new(
+        &request.query,
+        &request.replace,
+        request.use_regex,
+        request.case_sensitive,
+        request.preserve_case,
+    )?;
+
+    let mut pending = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut summary = ReplaceSummary {
+        files_changed: 0,
+        lines_changed: 0,
+        replacements: 0,
+    };
+
+    for selection in &request.files {
+        // The policy runs on the canonical path, which is also the one read
+        // and written: a symlink pointing out of the workspace is refused.
+        let Ok(path) = resolve_in_root(root, &selection.file)?.canonicalize() else {
+            conflicts.push(selection.file.clone());
+            continue;
+        };
+        workspace.check(&path, Access::Content)?;
+        let Some((bytes, content)) = read_text_file(&path) else {
+            conflicts.push(selection.file.clone());
+            continue;
+        };
+        if content_hash(&bytes) != selection.content_hash {
+            conflicts.push(selection.file.clone());
+            continue;
+        }
+
+        let only_lines: Option<HashSet<u32>> = selection
+            .lines
+            .as_ref()
+            .map(|l| l.iter().copied().collect());
+        let mut updated = String::with_capacity(content.len());
+        let mut lines_changed = 0;
+        let mut replacements = 0;
+        for (idx, piece) in content.split_inclusive('\n').enumerate() {
+            let (body, ending) = split_line_ending(piece);
+            let selected = only_lines
+                .as_ref()
+                .is_none_or(|lines| lines.contains(&((idx + 1) as u32)));
+            let (replaced, count) = if selected {
+                replacer.replace_line(body)
+            } else {
+                (body.to_string(), 0)
+            };
+            if count > 0 && replaced != body {
+                lines_changed += 1;
+                replacements += count;
+            }
+            updated.push_str(&replaced);
+            updated.push_str(ending);
+        }
+
+        if updated != content {
+            summary.files_changed += 1;
+            summary.lines_changed += lines_changed;
+            summary.replacements += replacements;
+            pending.push((path, bytes, updated));
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(format!(
+            "Files changed on disk since the preview: {}",
+            conflicts.join(", ")
+        ));
+    }
+
+    write_replaced(&pending)?;
+    Ok(summary)
+}
+
+/// Writes the new contents of `(path, original, updated)` entries in order.
+/// When a write fails, the files already written are restored to their
+/// original bytes.
+fn write_replaced(pending: &[(PathBuf, Vec<u8>, String)]) -> Result<(), String> {
+    for (idx, (path, _, updated)) in pending.iter().enumerate() {
+        if let Err(e) = write_atomic(path, updated.as_bytes()) {
+            let unrestored: Vec<String> = pending[..idx]
+                .iter()
+                .filter(|(done, original, _)| write_atomic(done, original).is_err())
+                .map(|(done, _, _)| done.display().to_string())
+                .collect();
+            let mut message = format!("Failed to write {}: {e}", path.display());
+            if !unrestored.is_empty() {
+                message.push_str(&format!("; could not restore {}", unrestored.join(", ")));
+            }
+            return Err(message);
+        }
+    }
+    Ok(())
+}
+
+/// Reads a UTF-8 text file, returning `None` for binary or undecodable files.
+fn read_text_file(path: &Path) -> Option<(Vec<u8>, String)> {
+    let bytes = fs::read(path).ok()?;
+    if walker::looks_binary(&bytes) {
+        return None;
+    }
+    let content = String::from_utf8(bytes.clone()).ok()?;
+    Some((bytes, content))
+}
+
+fn resolve_in_root(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative);
+    if relative.is_absolute()
+        || relative
+            .components()
+            .any(|c| matches!(c, Component::ParentDir | Component::Prefix(_)))
+    {
+        return Err(format!("{} is outside the search root", relative.display()));
+    }
+    Ok(root.join(relative))
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn split_line_ending(piece: &str) -> (&str, &str) {
+    if let Some(body) = piece.strip_suffix("\r\n") {
+        (body, "\r\n")
+    } else if let Some(body) = piece.strip_suffix('\n') {
+        (body, "\n")
+    } else {
+        (piece, "")
+    }
+}
+
+/// Case-preserving replacement: ALL CAPS, all lower and Capitalized matches
+/// carry their casing over to the replacement; anything else is left as is.
+fn match_case(matched: &str, replacement: &str) -> String {
+    let letters: Vec<char> = matched.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.is_empty() {
+        return replacement.to_string();
+    }
+    if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+        return replacement.to_uppercase();
+    }
+    if letters.iter().all(|c| c.is_lowercase()) {
+        return replacement.to_lowercase();
+    }
+    if letters[0].is_uppercase() && letters[1..].iter().all(|c| c.is_lowercase()) {
+        let mut chars = replacement.chars();
+        return match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        };
+    }
+    replacement.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::types::ReplaceFileSelection;
+
+    /// A fresh directory under the system temp dir, unique per test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nova-replace-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn opened(root: &Path) -> WorkspaceState {
+        let workspace = WorkspaceState::default();
+        workspace.record_pick(root).unwrap();
+        workspace.set_roots(&[root]).unwrap();
+        workspace
+    }
+
+    fn request(root: &Path, files: &[(&str, Option<Vec<u32>>)]) -> ApplyReplaceRequest {
+        ApplyReplaceRequest {
+            root: root.to_string_lossy().into_owned(),
+            query: "foo".to_string(),
+            replace: "bar".to_string(),
+            use_regex: false,
+            case_sensitive: true,
+            preserve_case: false,
+            files: files
+                .iter()
+                .map(|(file, lines)| ReplaceFileSelection {
+                    file: file.to_string(),
+                    content_hash: content_hash(&fs::read(root.join(file)).unwrap()),
+                    lines: lines.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn replaces_selected_lines_and_keeps_line_endings() {
+        let root = scratch("apply");
+        fs::write(root.join("a.txt"), "foo one\r\nfoo two\r\nfoo three\r\n").unwrap();
+        fs::write(root.join("b.txt"), "foo foo\n").unwrap();
+        let workspace = opened(&root);
+
+        let summary = apply_replacements(
+            &workspace,
+            &root,
+            &request(&root, &[("a.txt", Some(vec![1, 3])), ("b.txt", None)]),
+        )
+        .unwrap();
+
+        assert_eq!(summary.files_changed, 2);
+        assert_eq!(summary.lines_changed, 3);
+        assert_eq!(summary.replacements, 4);
+        assert_eq!(
+            fs::read_to_string(root.join("a.txt")).unwrap(),
+            "bar one\r\nfoo two\r\nbar three\r\n"
+        );
+        assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "bar bar\n");
+    }
+
+    #[test]
+    fn writes_nothing_when_a_file_changed_since_the_preview() {
+        let root = scratch("conflict");
+        fs::write(root.join("a.txt"), "foo\n").unwrap();
+        fs::write(root.join("b.txt"), "foo\n").unwrap();
+        let workspace = opened(&root);
+        let request = request(&root, &[("a.txt", None), ("b.txt", None)]);
+        fs::write(root.join("b.txt"), "foo changed\n").unwrap();
+
+        let error = apply_replacements(&workspace, &root, &request).unwrap_err();
+
+        assert!(error.contains("b.txt"), "{error}");
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "foo\n");
+        assert_eq!(
+            fs::read_to_string(root.join("b.txt")).unwrap(),
+            "foo changed\n"
+        );
+    }
+
+    #[test]
+    fn restores_written_files_when_a_later_write_fails() {
+        let root = scratch("rollback");
+        let first = root.join("a.txt");
+        fs::write(&first, "foo\n").unwrap();
+        let pending = vec![
+            (first.clone(), b"foo\n".to_vec(), "bar\n".to_string()),
+            (
+                root.join("missing/b.txt"),
+                b"foo\n".to_vec(),
+                "bar\n".to_string(),
+            ),
+        ];
+
+        let error = write_replaced(&pending).unwrap_err();
+
+        assert!(error.contains("b.txt"), "{error}");
+        assert!(!error.contains("could not restore"), "{error}");
+        assert_eq!(fs::read_to_string(&first).unwrap(), "foo\n");
+    }
+
+    #[test]
+    fn rejects_paths_leaving_the_search_root() {
+        let root = scratch("traversal");
+        let workspace = opened(&root);
+        let mut request = request(&root, &[]);
+        request.files.push(ReplaceFileSelection {
+            file: "../outside.txt".to_string(),
+            content_hash: String::new(),
+            lines: None,
+        });
+
+        assert!(apply_replacements(&workspace, &root, &request).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_pointing_out_of_the_workspace() {
+        let dir = scratch("escape");
+        let root = dir.join("root");
+        fs::create_dir_all(&root).unwrap();
+        let outside = dir.join("outside.txt");
+        fs::write(&outside, "foo\n").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link.txt")).unwrap();
+        let workspace = opened(&root);
+
+        let error = apply_replacements(&workspace, &root, &request(&root, &[("link.txt", None)]))
+            .unwrap_err();
+
+        assert!(error.starts_with("outside-workspace: "), "{error}");
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "foo\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_symlinks_and_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = scratch("metadata");
+        let target = root.join("target.sh");
+        fs::write(&target, "foo\n").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o750)).unwrap();
+        std::os::unix::fs::symlink(&target, root.join("link.sh")).unwrap();
+        let workspace = opened(&root);
+
+        apply_replacements(&workspace, &root, &request(&root, &[("link.sh", None)])).unwrap();
+
+        let link = fs::symlink_metadata(root.join("link.sh")).unwrap();
+        assert!(link.file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "bar\n");
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+/// Search request payload
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchFilesRequest {
+    /// Client-chosen id echoed in every event of this search. Generated by
+    /// the backend when omitted.
+    #[serde(default)]
+    pub search_id: Option<String>,
+    pub root: String,
+    pub query: String,
+    pub use_regex: bool,
+    pub case_sensitive: bool,
+    /// Glob patterns a file must match to be searched (e.g. `src/**/*.rs`).
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Glob patterns excluded on top of `.gitignore`/`.ignore` rules.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Switches the search into replace mode: instead of `search-hits` events
+    /// a `search-replace-preview` event is emitted per file.
+    #[serde(default)]
+    pub replace: Option<String>,
+    /// Adapts each replacement to the casing of the text it replaces.
+    #[serde(default)]
+    pub preserve_case: bool,
+    /// Number of lines before each hit to include as context.
+    #[serde(default)]
+    pub context_before: usize,
+    /// Number of lines after each hit to include as context.
+    #[serde(default)]
+    pub context_after: usize,
+    /// Matches the query against whole files so a pattern can span lines.
+    /// Replace previews stay line based.
+    #[serde(default)]
+    pub multiline: bool,
+}
+
+/// Search result hit for project-wide search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub file: String,
+    pub line: u32,
+    /// 1-based column in UTF-16 code units, as used by Monaco.
+    pub column: u32,
+    /// Position just past the match. Differs from `line` only for
+    /// multiline matches.
+    pub end_line: u32,
+    pub end_column: u32,
+    /// Match offsets in bytes, chars and UTF-16 code units. `start` is
+    /// relative to `line`, `end` to `end_line`.
+    pub byte_range: HitRange,
+    pub char_range: HitRange,
+    pub utf16_range: HitRange,
+    pub match_text: String,
+    /// Text of the first matched line.
+    pub line_text: String,
+    #[serde(default)]
+    pub context_before: Vec<String>,
+    #[serde(default)]
+    pub context_after: Vec<String>,
+}
+
+/// Zero-based, end-exclusive offsets of a match.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HitRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl HitRange {
+    pub fn new(start: u32, end: u32) -> Self {
+        Self { start, end }
+    }
+}
+
+/// One line of a replace preview.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacePreviewLine {
+    pub line: u32,
+    pub original: String,
+    pub replaced: String,
+    pub match_count: u32,
+}
+
+/// Replace preview for a single file. `content_hash` identifies the file
+/// contents the preview was computed from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReplacePreview {
+    pub file: String,
+    pub content_hash: String,
+    pub lines: Vec<ReplacePreviewLine>,
+}
+
+/// A file chosen from the preview. When `lines` is set only those lines
+/// are rewritten.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplaceFileSelection {
+    pub file: String,
+    pub content_hash: String,
+    #[serde(default)]
+    pub lines: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApplyReplaceRequest {
+    pub root: String,
+    pub query: String,
+    pub replace: String,
+    pub use_regex: bool,
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub preserve_case: bool,
+    pub files: Vec<ReplaceFileSelection>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceSummary {
+    pub files_changed: u32,
+    pub lines_changed: u32,
+    pub replacements: u32,
+}
+
+// Event payloads. Every search event carries the id of the search that
+// produced it so concurrent searches can share the same event names.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHitsEvent {
+    pub search_id: String,
+    pub hits: Vec<SearchHit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchProgressEvent {
+    pub search_id: String,
+    pub files_scanned: usize,
+    pub files_total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplacePreviewEvent {
+    pub search_id: String,
+    #[serde(flatten)]
+    pub preview: FileReplacePreview,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchCompleteEvent {
+    pub search_id: String,
+    pub result_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchCancelledEvent {
+    pub search_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchErrorEvent {
+    pub search_id: String,
+    pub message: String,
+}
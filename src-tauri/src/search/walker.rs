@@ -0,0 +1,104 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use ignore::{overrides::OverrideBuilder, WalkBuilder, WalkState};
+
+/// Number of leading bytes inspected when deciding whether a file is binary.
+pub const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+/// Builds a walker over `root` that honours `.gitignore`, `.ignore`,
+/// `.git/info/exclude` and the global git excludes file, plus the user's
+/// include/exclude globs. Hidden files and directories are skipped.
+pub fn build_walker(
+    root: &Path,
+    include: &[String],
+    exclude: &[String],
+) -> Result<WalkBuilder, String> {
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in include.iter().filter(|g| !g.trim().is_empty()) {
+        overrides
+            .add(glob.trim())
+            .map_err(|e| format!("Invalid include pattern {glob}: {e}"))?;
+    }
+    for glob in exclude.iter().filter(|g| !g.trim().is_empty()) {
+        overrides
+            .add(&format!("!{}", glob.trim()))
+            .map_err(|e| format!("Invalid exclude pattern {glob}: {e}"))?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|e| format!("Invalid search patterns: {e}"))?;
+
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(true)
+        .ignore(true)
+        .git_ignore(true)
+        .git_global(true)
+        .git_exclude(true)
+        .parents(true)
+        // Respect .gitignore even when the folder is not (yet) a git repository.
+        .require_git(false)
+        .follow_links(false)
+        .overrides(overrides);
+    Ok(builder)
+}
+
+/// A file or directory found by the walker.
+pub struct WalkedEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+/// Walks the tree on all cores and returns every file path, sorted so that
+/// results come out in the same order regardless of thread scheduling.
+/// Stops early (returning what was found so far) once `cancelled` is set.
+pub fn collect_files(builder: &WalkBuilder, cancelled: &AtomicBool) -> Vec<PathBuf> {
+    collect_entries(builder, cancelled)
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| entry.path)
+        .collect()
+}
+
+/// Like `collect_files`, but also returns the directories walked, including
+/// the root itself.
+pub fn collect_entries(builder: &WalkBuilder, cancelled: &AtomicBool) -> Vec<WalkedEntry> {
+    let entries = Mutex::new(Vec::new());
+    builder.build_parallel().run(|| {
+        let entries = &entries;
+        Box::new(move |entry| {
+            if cancelled.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+            if let Ok(entry) = entry {
+                let file_type = entry.file_type();
+                let is_dir = file_type.is_some_and(|t| t.is_dir());
+                if is_dir || file_type.is_some_and(|t| t.is_file()) {
+                    entries
+                        .lock()
+                        .expect("walker entries poisoned")
+                        .push(WalkedEntry {
+                            path: entry.into_path(),
+                            is_dir,
+                        });
+                }
+            }
+            WalkState::Continue
+        })
+    });
+    let mut entries = entries.into_inner().expect("walker entries poisoned");
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+/// Content-based binary detection: a NUL byte within the first
+/// `BINARY_SNIFF_LEN` bytes marks the file as binary, like git does.
+pub fn looks_binary(prefix: &[u8]) -> bool {
+    prefix[..prefix.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
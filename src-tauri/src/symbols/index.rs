@@ -0,0 +1,178 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rayon::prelude::*;
+use tree_sitter::Parser;
+
+use crate::{quick_open::index::FileIndex, search::walker};
+
+use super::{parser, types::WorkspaceSymbol};
+
+/// Files above this size are skipped; they are mostly generated or minified.
+const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+/// Progress is reported after every this many parsed files.
+const PROGRESS_EVERY: usize = 64;
+
+/// Symbols of every parsable file below a workspace root. The file list is a
+/// quick open `FileIndex`, so the same ignore rules apply and watcher events
+/// are resolved the same way.
+pub struct SymbolIndex {
+    files: FileIndex,
+    symbols: BTreeMap<String, Vec<WorkspaceSymbol>>,
+}
+
+/// Symbols parsed from files, keyed by relative path.
+pub type Parsed = Vec<(String, Vec<WorkspaceSymbol>)>;
+
+impl SymbolIndex {
+    /// Walks `root` and parses all files on all cores. `progress` receives
+    /// the number of files parsed so far and the total. Fails when the
+    /// symbol query of a language present in the tree doesn't compile.
+    pub fn build(root: &Path, progress: impl Fn(usize, usize) + Sync) -> Result<Self, String> {
+        let files = FileIndex::build(root)?;
+        let parsable: Vec<&str> = files.files().filter(|f| is_parsable(f)).collect();
+        let total = parsable.len();
+        let done = AtomicUsize::new(0);
+        progress(0, total);
+
+        let symbols = parsable
+            .par_iter()
+            .map_init(Parser::new, |parser, relative| {
+                let symbols = parse_file(parser, root, relative);
+                let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                if done % PROGRESS_EVERY == 0 || done == total {
+                    progress(done, total);
+                }
+                symbols.map(|symbols| (relative.to_string(), symbols))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { files, symbols })
+    }
+
+    pub fn root(&self) -> &Path {
+        self.files.root()
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn symbol_count(&self) -> usize {
+        self.symbols.values().map(Vec::len).sum()
+    }
+
+    /// All symbols, ordered by file and then position.
+    pub fn symbols(&self) -> impl Iterator<Item = &WorkspaceSymbol> {
+        self.symbols.values().flatten()
+    }
+
+    pub fn file_symbols(&self, relative: &str) -> &[WorkspaceSymbol] {
+        self.symbols.get(relative).map_or(&[], Vec::as_slice)
+    }
+
+    /// Re-parses whatever `paths` now contain, dropping symbols of paths
+    /// that are gone. Returns `true` when an ignore file changed and the
+    /// index has to be rebuilt instead.
+    pub fn apply(&mut self, paths: &[PathBuf]) -> Result<bool, String> {
+        let Some(files) = self.update(paths) else {
+            return Ok(true);
+        };
+        let parsed = parse_files(self.root(), &files)?;
+        self.insert_parsed(parsed);
+        Ok(false)
+    }
+
+    /// The first half of `apply`: updates the file list and drops the
+    /// symbols of everything below `paths`. Returns the files to parse
+    /// again, or `None` when an ignore file changed and the index has to be
+    /// rebuilt instead.
+    pub fn update(&mut self, paths: &[PathBuf]) -> Option<Vec<String>> {
+        if self.files.apply(paths) {
+            return None;
+        }
+
+        let mut added = Vec::new();
+        for path in paths {
+            let Some(relative) = self.files.relative(path) else {
+                continue;
+            };
+            if relative.is_empty() {
+                continue;
+            }
+
+            // The path may have been a directory: everything below it goes.
+            let prefix = format!("{relative}/");
+            self.symbols
+                .retain(|file, _| *file != relative && !file.starts_with(&prefix));
+            added.extend(
+                self.files
+                    .files()
+                    .filter(|f| (*f == relative || f.starts_with(&prefix)) && is_parsable(f))
+                    .map(str::to_string),
+            );
+        }
+        added.sort_unstable();
+        added.dedup();
+        Some(added)
+    }
+
+    /// The second half of `apply`. Files that are no longer listed, or
+    /// already have symbols again, are skipped: the index changed since the
+    /// files were handed out.
+    pub fn insert_parsed(&mut self, parsed: Parsed) {
+        for (file, symbols) in parsed {
+            if self.files.contains(&file) {
+                self.symbols.entry(file).or_insert(symbols);
+            }
+        }
+    }
+}
+
+/// Parses `files` below `root`; runs without any index borrowed, so callers
+/// can do it outside their locks.
+pub fn parse_files(root: &Path, files: &[String]) -> Result<Parsed, String> {
+    let mut parser = Parser::new();
+    files
+        .iter()
+        .map(|file| parse_file(&mut parser, root, file).map(|symbols| (file.clone(), symbols)))
+        .collect()
+}
+
+fn is_parsable(relative: &str) -> bool {
+    parser::grammar_for(Path::new(relative)).is_some()
+}
+
+/// Reads and parses one file. Unreadable, oversized and binary files yield
+/// no symbols; a language whose query doesn't compile is an error.
+fn parse_file(
+    parser: &mut Parser,
+    root: &Path,
+    relative: &str,
+) -> Result<Vec<WorkspaceSymbol>, String> {
+    let path = root.join(relative);
+    let Some(grammar) = parser::grammar_for(&path) else {
+        return Ok(Vec::new());
+    };
+    let grammar = grammar.as_ref().map_err(Clone::clone)?;
+    let Ok(file) = fs::File::open(&path) else {
+        return Ok(Vec::new());
+    };
+    if !file.metadata().is_ok_and(|m| m.len() <= MAX_FILE_SIZE) {
+        return Ok(Vec::new());
+    }
+
+    let mut bytes = Vec::new();
+    if file.take(MAX_FILE_SIZE).read_to_end(&mut bytes).is_err() || walker::looks_binary(&bytes) {
+        return Ok(Vec::new());
+    }
+    let source = String::from_utf8_lossy(&bytes);
+    let mut symbols = parser::extract_symbols(parser, grammar, relative, &source);
+    symbols.sort_by_key(|s| (s.line, s.column));
+    Ok(symbols)
+}
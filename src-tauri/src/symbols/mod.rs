@@ -0,0 +1,267 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    mem,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use nucleo_matcher::{
+    pattern::{CaseMatching, Normalization, Pattern},
+    Config, Matcher, Utf32Str,
+};
+use tauri::{AppHandle, Emitter};
+
+pub mod index;
+pub mod parser;
+pub mod types;
+
+use crate::{quick_open::utf16_positions, watcher::types::FileChangeBatch};
+use index::SymbolIndex;
+use types::{
+    SymbolIndexProgressEvent, SymbolIndexStatus, SymbolMatch, SymbolQueryRequest, WorkspaceSymbol,
+};
+
+pub const DEFAULT_LIMIT: usize = 200;
+
+/// Workspace symbol indexes keyed by root. Indexes are built on a background
+/// thread and then kept current from file watcher events.
+#[derive(Clone, Default)]
+pub struct SymbolState {
+    inner: Arc<Mutex<Indexes>>,
+}
+
+#[derive(Default)]
+struct Indexes {
+    ready: HashMap<PathBuf, SymbolIndex>,
+    /// Roots being built, with the watcher paths reported meanwhile. They are
+    /// applied once the build finishes.
+    building: HashMap<PathBuf, Vec<PathBuf>>,
+    /// Roots whose last build or update failed, with the error.
+    failed: HashMap<PathBuf, String>,
+}
+
+impl SymbolState {
+    /// Starts indexing `root` in the background unless it is already indexed
+    /// or being indexed. `rebuild` discards an existing index. Progress is
+    /// reported through `symbol-index-progress` events, the end through a
+    /// `symbol-index-status` event.
+    pub fn start_indexing(&self, app: &AppHandle, root: &Path, rebuild: bool) -> SymbolIndexStatus {
+        {
+            let mut inner = self.inner.lock().expect("symbol indexes poisoned");
+            if !inner.building.contains_key(root) && (rebuild || !inner.ready.contains_key(root)) {
+                inner.building.insert(root.to_path_buf(), Vec::new());
+                let state = self.clone();
+                let app = app.clone();
+                let root = root.to_path_buf();
+                thread::spawn(move || state.build(&app, &root));
+            }
+        }
+        self.status(root)
+    }
+
+    pub fn status(&self, root: &Path) -> SymbolIndexStatus {
+        let inner = self.inner.lock().expect("symbol indexes poisoned");
+        let index = inner.ready.get(root);
+        SymbolIndexStatus {
+            root: display(root),
+            indexing: inner.building.contains_key(root),
+            error: inner.failed.get(root).cloned(),
+            file_count: index.map_or(0, SymbolIndex::file_count),
+            symbol_count: index.map_or(0, SymbolIndex::symbol_count),
+        }
+    }
+
+    /// Drops the indexes of roots no longer in the workspace. A build still
+    /// running for one of them is discarded when it finishes.
+    pub fn retain_roots(&self, roots: &[PathBuf]) {
+        let mut inner = self.inner.lock().expect("symbol indexes poisoned");
+        inner.ready.retain(|root, _| roots.contains(root));
+        inner.building.retain(|root, _| roots.contains(root));
+        inner.failed.retain(|root, _| roots.contains(root));
+    }
+
+    /// Builds the index of `root` and publishes it, or records why that
+    /// failed. Either way the root is no longer `building` afterwards.
+    fn build(&self, app: &AppHandle, root: &Path) {
+        let root_name = display(root);
+        let error = loop {
+            let built = SymbolIndex::build(root, |files_indexed, files_total| {
+                let _ = app.emit(
+                    "symbol-index-progress",
+                    SymbolIndexProgressEvent {
+                        root: root_name.clone(),
+                        files_indexed,
+                        files_total,
+                    },
+                );
+            });
+            match built.and_then(|index| self.catch_up(root, index)) {
+                Ok(true) => break None,
+                // An ignore file changed meanwhile: walk the root again.
+                Ok(false) => continue,
+                Err(error) => break Some(error),
+            }
+        };
+
+        if let Some(error) = error {
+            let mut inner = self.inner.lock().expect("symbol indexes poisoned");
+            if inner.building.remove(root).is_some() {
+                inner.failed.insert(root.to_path_buf(), error);
+            }
+        }
+        let _ = app.emit("symbol-index-status", self.status(root));
+    }
+
+    /// Applies the paths reported while `index` was built, parsing outside
+    /// the lock, and publishes the index once none are left. Returns `false`
+    /// when an ignore file changed and the root has to be walked again. The
+    /// index is dropped if the root left the workspace meanwhile.
+    fn catch_up(&self, root: &Path, mut index: SymbolIndex) -> Result<bool, String> {
+        loop {
+            let pending = {
+                let mut inner = self.inner.lock().expect("symbol indexes poisoned");
+                let Some(pending) = inner.building.get_mut(root).map(mem::take) else {
+                    return Ok(true);
+                };
+                if pending.is_empty() {
+                    inner.building.remove(root);
+                    inner.failed.remove(root);
+                    inner.ready.insert(root.to_path_buf(), index);
+                    return Ok(true);
+                }
+                pending
+            };
+            if index.apply(&pending)? {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Returns the symbols whose name fuzzy-matches `request.query`, highest
+    /// score first. An empty query lists symbols in file and position order.
+    /// Roots that are not indexed (yet) yield no symbols.
+    pub fn query(&self, root: &Path, request: &SymbolQueryRequest) -> Vec<SymbolMatch> {
+        let limit = request.limit.unwrap_or(DEFAULT_LIMIT);
+        let inner = self.inner.lock().expect("symbol indexes poisoned");
+        let Some(index) = inner.ready.get(root) else {
+            return Vec::new();
+        };
+
+        let candidates: Box<dyn Iterator<Item = &WorkspaceSymbol>> = match &request.file {
+            Some(file) => Box::new(index.file_symbols(&file.replace('\\', "/")).iter()),
+            None => Box::new(index.symbols()),
+        };
+        let candidates =
+            candidates.filter(|s| request.kinds.is_empty() || request.kinds.contains(&s.kind));
+
+        if request.query.trim().is_empty() {
+            return candidates
+                .take(limit)
+                .map(|symbol| to_match(symbol, 0, &[]))
+                .collect();
+        }
+
+        let pattern = Pattern::parse(&request.query, CaseMatching::Smart, Normalization::Smart);
+        let mut matcher = Matcher::new(Config::DEFAULT);
+        let mut buf = Vec::new();
+        let mut scored: Vec<(u32, &WorkspaceSymbol)> = candidates
+            .filter_map(|symbol| {
+                pattern
+                    .score(Utf32Str::new(&symbol.name, &mut buf), &mut matcher)
+                    .map(|score| (score, symbol))
+            })
+            .collect();
+        // Higher scores first; among equals, shorter names are closer matches.
+        scored.sort_by_key(|(score, symbol)| (Reverse(*score), symbol.name.len()));
+        scored.truncate(limit);
+
+        let mut indices = Vec::new();
+        scored
+            .into_iter()
+            .map(|(score, symbol)| {
+                indices.clear();
+                pattern.indices(
+                    Utf32Str::new(&symbol.name, &mut buf),
+                    &mut matcher,
+                    &mut indices,
+                );
+                indices.sort_unstable();
+                indices.dedup();
+                to_match(symbol, score, &indices)
+            })
+            .collect()
+    }
+
+    /// Applies a file watcher batch to every index it concerns: changed files
+    /// are parsed again, removed ones dropped. An edited ignore file triggers
+    /// a rebuild of the affected index. Files are parsed without the lock,
+    /// so queries don't wait for them.
+    pub fn apply_changes(&self, app: &AppHandle, batch: &FileChangeBatch) {
+        let paths = batch.paths();
+        if paths.is_empty() {
+            return;
+        }
+
+        let mut stale = Vec::new();
+        let mut to_parse = Vec::new();
+        {
+            let mut inner = self.inner.lock().expect("symbol indexes poisoned");
+            let Indexes {
+                ready, building, ..
+            } = &mut *inner;
+            for (root, pending) in building.iter_mut() {
+                pending.extend(paths.iter().filter(|p| p.starts_with(root)).cloned());
+            }
+            for (root, index) in ready.iter_mut() {
+                // A running rebuild replaces this index and replays the paths.
+                if building.contains_key(root) {
+                    continue;
+                }
+                if !paths.iter().any(|p| p.starts_with(root)) {
+                    continue;
+                }
+                match index.update(&paths) {
+                    None => stale.push(root.clone()),
+                    Some(files) if !files.is_empty() => to_parse.push((root.clone(), files)),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        for (root, files) in to_parse {
+            let parsed = index::parse_files(&root, &files);
+            let mut inner = self.inner.lock().expect("symbol indexes poisoned");
+            match parsed {
+                Ok(parsed) => {
+                    if let Some(index) = inner.ready.get_mut(&root) {
+                        index.insert_parsed(parsed);
+                    }
+                }
+                Err(error) => {
+                    inner.ready.remove(&root);
+                    inner.failed.insert(root.clone(), error);
+                    drop(inner);
+                    let _ = app.emit("symbol-index-status", self.status(&root));
+                }
+            }
+        }
+
+        for root in stale {
+            self.start_indexing(app, &root, true);
+        }
+    }
+}
+
+fn display(root: &Path) -> String {
+    root.to_string_lossy().replace('\\', "/")
+}
+
+fn to_match(symbol: &WorkspaceSymbol, score: u32, char_indices: &[u32]) -> SymbolMatch {
+    SymbolMatch {
+        symbol: symbol.clone(),
+        score,
+        positions: utf16_positions(&symbol.name, char_indices),
+    }
+}
@@ -0,0 +1,218 @@
+use std::{collections::HashMap, ops::Range, path::Path};
+
+use once_cell::sync::Lazy;
+use tree_sitter::{Language, Node, Parser, Query, QueryCursor, StreamingIterator};
+
+use crate::symbols::types::{SymbolKind, WorkspaceSymbol};
+
+/// A tree-sitter grammar together with its symbol query.
+pub struct Grammar {
+    language: Language,
+    query: Query,
+    name_capture: u32,
+}
+
+impl Grammar {
+    /// Compiles the query. A broken one is an error for the files of this
+    /// language rather than a panic in the indexing thread.
+    fn new(name: &str, language: Language, source: &str) -> Result<Self, String> {
+        let query = Query::new(&language, source)
+            .map_err(|e| format!("Invalid {name} symbol query: {e}"))?;
+        let name_capture = query
+            .capture_index_for_name("name")
+            .ok_or_else(|| format!("The {name} symbol query has no @name capture"))?;
+        Ok(Self {
+            language,
+            query,
+            name_capture,
+        })
+    }
+}
+
+type LazyGrammar = Lazy<Result<Grammar, String>>;
+
+const JAVASCRIPT_QUERY: &str = include_str!("queries/javascript.scm");
+const TYPESCRIPT_QUERY: &str = include_str!("queries/typescript.scm");
+
+static RUST: LazyGrammar = Lazy::new(|| {
+    Grammar::new(
+        "Rust",
+        tree_sitter_rust::LANGUAGE.into(),
+        include_str!("queries/rust.scm"),
+    )
+});
+static JAVASCRIPT: LazyGrammar = Lazy::new(|| {
+    Grammar::new(
+        "JavaScript",
+        tree_sitter_javascript::LANGUAGE.into(),
+        JAVASCRIPT_QUERY,
+    )
+});
+static TYPESCRIPT: LazyGrammar = Lazy::new(|| {
+    Grammar::new(
+        "TypeScript",
+        tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+        &format!("{JAVASCRIPT_QUERY}\n{TYPESCRIPT_QUERY}"),
+    )
+});
+static TSX: LazyGrammar = Lazy::new(|| {
+    Grammar::new(
+        "TSX",
+        tree_sitter_typescript::LANGUAGE_TSX.into(),
+        &format!("{JAVASCRIPT_QUERY}\n{TYPESCRIPT_QUERY}"),
+    )
+});
+static PYTHON: LazyGrammar = Lazy::new(|| {
+    Grammar::new(
+        "Python",
+        tree_sitter_python::LANGUAGE.into(),
+        include_str!("queries/python.scm"),
+    )
+});
+static GO: LazyGrammar = Lazy::new(|| {
+    Grammar::new(
+        "Go",
+        tree_sitter_go::LANGUAGE.into(),
+        include_str!("queries/go.scm"),
+    )
+});
+static MARKDOWN: LazyGrammar = Lazy::new(|| {
+    Grammar::new(
+        "Markdown",
+        tree_sitter_md::LANGUAGE.into(),
+        include_str!("queries/markdown.scm"),
+    )
+});
+
+/// Picks the grammar for a file by its extension; `Err` when its query
+/// doesn't compile.
+pub fn grammar_for(path: &Path) -> Option<&'static Result<Grammar, String>> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let grammar: &LazyGrammar = match ext.as_str() {
+        "rs" => &RUST,
+        "js" | "mjs" | "cjs" | "jsx" => &JAVASCRIPT,
+        "ts" | "mts" | "cts" => &TYPESCRIPT,
+        "tsx" => &TSX,
+        "py" | "pyi" => &PYTHON,
+        "go" => &GO,
+        "md" | "markdown" => &MARKDOWN,
+        _ => return None,
+    };
+    Some(&**grammar)
+}
+
+struct Definition {
+    kind: Option<SymbolKind>,
+    name: String,
+    name_start: Position,
+    range: Range<usize>,
+    end: Position,
+}
+
+/// Parses `source` and returns the symbols it defines. `parser` is reused
+/// between calls to avoid re-allocating its state.
+pub fn extract_symbols(
+    parser: &mut Parser,
+    grammar: &Grammar,
+    file: &str,
+    source: &str,
+) -> Vec<WorkspaceSymbol> {
+    if parser.set_language(&grammar.language).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+
+    // A node matched by several patterns keeps the first pattern's kind.
+    let mut by_node: HashMap<usize, (usize, Definition)> = HashMap::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&grammar.query, tree.root_node(), source.as_bytes());
+    while let Some(m) = matches.next() {
+        let mut name_node = None;
+        let mut definition = None;
+        for capture in m.captures {
+            if capture.index == grammar.name_capture {
+                name_node = Some(capture.node);
+            } else {
+                let capture_name = grammar.query.capture_names()[capture.index as usize];
+                definition = Some((capture.node, SymbolKind::from_capture(capture_name)));
+            }
+        }
+        let (Some(name_node), Some((node, kind))) = (name_node, definition) else {
+            continue;
+        };
+        let name = source[name_node.byte_range()].trim().to_string();
+        if name.is_empty() {
+            continue;
+        }
+
+        let entry = Definition {
+            kind,
+            name,
+            name_start: Position::start_of(source, name_node),
+            range: node.byte_range(),
+            end: Position::end_of(source, node),
+        };
+        match by_node.get(&node.id()) {
+            Some((pattern, _)) if *pattern <= m.pattern_index => {}
+            _ => {
+                by_node.insert(node.id(), (m.pattern_index, entry));
+            }
+        }
+    }
+
+    let mut definitions: Vec<Definition> = by_node.into_values().map(|(_, d)| d).collect();
+    definitions.sort_by_key(|d| (d.range.start, std::cmp::Reverse(d.range.end)));
+
+    definitions
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, def)| {
+            let kind = def.kind?;
+            // Sorted by start, so the innermost enclosing definition is the
+            // last earlier one that still contains this one.
+            let container_name = definitions[..idx]
+                .iter()
+                .rev()
+                .find(|outer| outer.range.end >= def.range.end)
+                .map(|outer| outer.name.clone());
+            Some(WorkspaceSymbol {
+                name: def.name.clone(),
+                kind,
+                file: file.to_string(),
+                line: def.name_start.line,
+                column: def.name_start.column,
+                end_line: def.end.line,
+                end_column: def.end.column,
+                container_name,
+            })
+        })
+        .collect()
+}
+
+/// 1-based line and UTF-16 column.
+struct Position {
+    line: u32,
+    column: u32,
+}
+
+impl Position {
+    fn start_of(source: &str, node: Node) -> Self {
+        let point = node.start_position();
+        Self::at(source, node.start_byte(), point.row, point.column)
+    }
+
+    fn end_of(source: &str, node: Node) -> Self {
+        let point = node.end_position();
+        Self::at(source, node.end_byte(), point.row, point.column)
+    }
+
+    fn at(source: &str, byte: usize, row: usize, byte_column: usize) -> Self {
+        let line_start = byte - byte_column;
+        Self {
+            line: row as u32 + 1,
+            column: source[line_start..byte].encode_utf16().count() as u32 + 1,
+        }
+    }
+}
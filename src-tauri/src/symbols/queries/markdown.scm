@@ -0,0 +1,7 @@
+; Sections span their subsections, so nested headings get their parent
+; heading as container.
+(section
+  [
+    (atx_heading heading_content: (_) @name)
+    (setext_heading heading_content: (_) @name)
+  ]) @heading
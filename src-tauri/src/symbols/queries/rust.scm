@@ -0,0 +1,25 @@
+; Methods come before plain functions: a node matched by several patterns
+; keeps the kind of the first one.
+(impl_item
+  body: (declaration_list
+    (function_item name: (identifier) @name) @method))
+(trait_item
+  body: (declaration_list
+    [
+      (function_item name: (identifier) @name)
+      (function_signature_item name: (identifier) @name)
+    ] @method))
+
+(function_item name: (identifier) @name) @function
+(struct_item name: (type_identifier) @name) @struct
+(union_item name: (type_identifier) @name) @struct
+(enum_item name: (type_identifier) @name) @enum
+(trait_item name: (type_identifier) @name) @trait
+(type_item name: (type_identifier) @name) @type
+(mod_item name: (identifier) @name) @module
+(macro_definition name: (identifier) @name) @macro
+(const_item name: (identifier) @name) @constant
+(static_item name: (identifier) @name) @constant
+
+; Impl blocks only name the methods inside them.
+(impl_item type: (_) @name) @scope
@@ -0,0 +1,10 @@
+; Appended to the JavaScript query, whose node types TypeScript shares.
+(abstract_class_declaration name: (type_identifier) @name) @class
+(interface_declaration name: (type_identifier) @name) @interface
+(type_alias_declaration name: (type_identifier) @name) @type
+(enum_declaration name: (identifier) @name) @enum
+(method_signature name: (_) @name) @method
+(abstract_method_signature name: (_) @name) @method
+(function_signature name: (identifier) @name) @function
+(internal_module name: (_) @name) @module
+(module name: (_) @name) @module
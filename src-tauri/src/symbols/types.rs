@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+    Struct,
+    Enum,
+    Interface,
+    Trait,
+    Type,
+    Module,
+    Constant,
+    Macro,
+    Heading,
+}
+
+impl SymbolKind {
+    /// Maps a query capture name to its kind.
+    pub fn from_capture(name: &str) -> Option<Self> {
+        Some(match name {
+            "function" => Self::Function,
+            "method" => Self::Method,
+            "class" => Self::Class,
+            "struct" => Self::Struct,
+            "enum" => Self::Enum,
+            "interface" => Self::Interface,
+            "trait" => Self::Trait,
+            "type" => Self::Type,
+            "module" => Self::Module,
+            "constant" => Self::Constant,
+            "macro" => Self::Macro,
+            "heading" => Self::Heading,
+            _ => return None,
+        })
+    }
+}
+
+/// A symbol defined somewhere in the workspace. `line`/`column` point at the
+/// symbol's name, the end position at the end of its definition. Lines are
+/// 1-based, columns 1-based UTF-16 like `SearchHit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// Name of the enclosing symbol, e.g. the type a method belongs to.
+    pub container_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SymbolQueryRequest {
+    pub root: String,
+    pub query: String,
+    /// Restricts results to one file (relative path), e.g. for the outline.
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Kinds to return; empty means all.
+    #[serde(default)]
+    pub kinds: Vec<SymbolKind>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolMatch {
+    #[serde(flatten)]
+    pub symbol: WorkspaceSymbol,
+    pub score: u32,
+    /// Matched positions in `name`, as UTF-16 offsets.
+    pub positions: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolIndexStatus {
+    pub root: String,
+    pub indexing: bool,
+    /// Why the last build or update of the index failed.
+    pub error: Option<String>,
+    pub file_count: usize,
+    pub symbol_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolIndexProgressEvent {
+    pub root: String,
+    pub files_indexed: usize,
+    pub files_total: usize,
+}
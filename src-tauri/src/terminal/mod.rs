@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::Path,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tauri::AppHandle;
+
+pub mod session;
+pub mod types;
+
+use session::TerminalSession;
+use types::{CreateTerminalRequest, TerminalInfo};
+
+pub type TerminalResult<T> = Result<T, TerminalError>;
+
+#[derive(Debug)]
+pub enum TerminalError {
+    NotFound(u32),
+    Pty(String),
+    Io(String),
+    InvalidInput(String),
+}
+
+impl From<std::io::Error> for TerminalError {
+    fn from(value: std::io::Error) -> Self {
+        TerminalError::Io(value.to_string())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TerminalState {
+    sessions: Arc<Mutex<HashMap<u32, TerminalSession>>>,
+    next_id: Arc<AtomicU32>,
+}
+
+impl TerminalState {
+    pub fn create(
+        &self,
+        app: &AppHandle,
+        cwd: &Path,
+        request: &CreateTerminalRequest,
+    ) -> TerminalResult<TerminalInfo> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        session::spawn_session(app, self, id, cwd, request)
+    }
+
+    pub fn write(&self, id: u32, data: &str) -> TerminalResult<()> {
+        // The PTY write may block; it runs outside the sessions lock.
+        let writer = self
+            .sessions
+            .lock()
+            .expect("terminal sessions poisoned")
+            .get(&id)
+            .ok_or(TerminalError::NotFound(id))?
+            .writer();
+        let mut writer = writer.lock().expect("terminal writer poisoned");
+        writer.write_all(data.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn resize(&self, id: u32, cols: u16, rows: u16) -> TerminalResult<()> {
+        let mut sessions = self.sessions.lock().expect("terminal sessions poisoned");
+        let session = sessions.get_mut(&id).ok_or(TerminalError::NotFound(id))?;
+        session.resize(cols, rows)
+    }
+
+    pub fn kill(&self, id: u32) -> TerminalResult<()> {
+        let mut session = self.remove(id).ok_or(TerminalError::NotFound(id))?;
+        session.kill();
+        Ok(())
+    }
+
+    pub fn kill_all(&self) {
+        let sessions: Vec<TerminalSession> = self
+            .sessions
+            .lock()
+            .expect("terminal sessions poisoned")
+            .drain()
+            .map(|(_, session)| session)
+            .collect();
+        for mut session in sessions {
+            session.kill();
+        }
+    }
+
+    pub fn list(&self) -> Vec<TerminalInfo> {
+        let sessions = self.sessions.lock().expect("terminal sessions poisoned");
+        let mut infos: Vec<TerminalInfo> = sessions.values().map(|s| s.info.clone()).collect();
+        infos.sort_by_key(|info| info.id);
+        infos
+    }
+
+    fn insert(&self, id: u32, session: TerminalSession) {
+        self.sessions
+            .lock()
+            .expect("terminal sessions poisoned")
+            .insert(id, session);
+    }
+
+    fn remove(&self, id: u32) -> Option<TerminalSession> {
+        self.sessions
+            .lock()
+            .expect("terminal sessions poisoned")
+            .remove(&id)
+    }
+}
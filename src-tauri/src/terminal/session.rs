@@ -0,0 +1,180 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use tauri::{AppHandle, Emitter};
+
+use crate::terminal::{
+    types::{CreateTerminalRequest, TerminalExit, TerminalInfo, TerminalOutput},
+    TerminalError, TerminalResult, TerminalState,
+};
+
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+pub struct TerminalSession {
+    pub info: TerminalInfo,
+    master: Box<dyn MasterPty + Send>,
+    /// Locked on its own, so a blocked write doesn't hold up other sessions.
+    writer: SessionWriter,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+}
+
+pub type SessionWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+impl TerminalSession {
+    pub fn writer(&self) -> SessionWriter {
+        Arc::clone(&self.writer)
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> TerminalResult<()> {
+        self.master
+            .resize(pty_size(cols, rows))
+            .map_err(|e| TerminalError::Pty(e.to_string()))?;
+        self.info.cols = cols;
+        self.info.rows = rows;
+        Ok(())
+    }
+
+    pub fn kill(&mut self) {
+        let _ = self.killer.kill();
+    }
+}
+
+/// Starts the shell and registers the session in `state` before its reader
+/// thread runs, so a shell that exits at once is still removed again.
+pub fn spawn_session(
+    app: &AppHandle,
+    state: &TerminalState,
+    id: u32,
+    cwd: &Path,
+    request: &CreateTerminalRequest,
+) -> TerminalResult<TerminalInfo> {
+    if !cwd.is_dir() {
+        return Err(TerminalError::InvalidInput(format!(
+            "Terminal cwd {} is not a directory",
+            cwd.display()
+        )));
+    }
+
+    let pair = native_pty_system()
+        .openpty(pty_size(request.cols, request.rows))
+        .map_err(|e| TerminalError::Pty(e.to_string()))?;
+
+    let mut cmd = match &request.shell {
+        Some(shell) => {
+            let mut cmd = CommandBuilder::new(shell);
+            cmd.args(&request.args);
+            cmd
+        }
+        None => CommandBuilder::new_default_prog(),
+    };
+    cmd.cwd(cwd);
+    cmd.env("TERM", "xterm-256color");
+    cmd.env("COLORTERM", "truecolor");
+    cmd.env("TERM_PROGRAM", "nova-code");
+    for (key, value) in &request.env {
+        cmd.env(key, value);
+    }
+    let shell = request.shell.clone().unwrap_or_else(|| cmd.get_shell());
+
+    let mut child = pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| TerminalError::Pty(e.to_string()))?;
+    // The slave end belongs to the child now; keeping it open would prevent
+    // the reader from ever seeing EOF after the shell exits.
+    drop(pair.slave);
+
+    let mut reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| TerminalError::Pty(e.to_string()))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| TerminalError::Pty(e.to_string()))?;
+    let killer = child.clone_killer();
+
+    let info = TerminalInfo {
+        id,
+        cwd: cwd.to_string_lossy().replace('\\', "/"),
+        shell,
+        pid: child.process_id(),
+        cols: request.cols,
+        rows: request.rows,
+    };
+
+    state.insert(
+        id,
+        TerminalSession {
+            info: info.clone(),
+            master: pair.master,
+            writer: Arc::new(Mutex::new(writer)),
+            killer,
+        },
+    );
+
+    let handle = app.clone();
+    let state_for_thread = state.clone();
+    thread::spawn(move || {
+        let mut buf = [0u8; READ_BUFFER_SIZE];
+        let mut pending = Vec::new();
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    pending.extend_from_slice(&buf[..n]);
+                    let data = drain_utf8(&mut pending);
+                    if !data.is_empty() {
+                        let _ = handle.emit("terminal-output", TerminalOutput { id, data });
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+
+        let exit_code = child.wait().ok().map(|status| status.exit_code());
+        state_for_thread.remove(id);
+        let _ = handle.emit("terminal-exit", TerminalExit { id, exit_code });
+    });
+
+    Ok(info)
+}
+
+fn pty_size(cols: u16, rows: u16) -> PtySize {
+    PtySize {
+        rows: rows.max(1),
+        cols: cols.max(1),
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// Decodes as much of `pending` as possible, keeping an incomplete trailing
+/// UTF-8 sequence for the next read so multi-byte characters split across
+/// chunks are not replaced with U+FFFD.
+fn drain_utf8(pending: &mut Vec<u8>) -> String {
+    match std::str::from_utf8(pending) {
+        Ok(text) => {
+            let out = text.to_string();
+            pending.clear();
+            out
+        }
+        Err(err) if err.error_len().is_none() => {
+            let valid = err.valid_up_to();
+            let out = String::from_utf8_lossy(&pending[..valid]).into_owned();
+            pending.drain(..valid);
+            out
+        }
+        Err(_) => {
+            let out = String::from_utf8_lossy(pending).into_owned();
+            pending.clear();
+            out
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreateTerminalRequest {
+    pub cwd: String,
+    #[serde(default)]
+    pub shell: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerminalInfo {
+    pub id: u32,
+    pub cwd: String,
+    pub shell: String,
+    pub pid: Option<u32>,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerminalOutput {
+    pub id: u32,
+    pub data: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TerminalExit {
+    pub id: u32,
+    pub exit_code: Option<u32>,
+}
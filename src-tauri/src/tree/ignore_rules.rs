@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+
+/// Ignore rules in effect for one directory: the global git excludes file,
+/// `.git/info/exclude` of the workspace root and every `.gitignore`/`.ignore`
+/// between the root and the directory. Matching also checks parent
+/// directories, so entries below an ignored directory count as ignored.
+pub struct IgnoreRules {
+    /// Ordered from lowest to highest precedence.
+    matchers: Vec<Gitignore>,
+}
+
+impl IgnoreRules {
+    pub fn for_dir(root: &Path, dir: &Path) -> Self {
+        let mut matchers = Vec::new();
+        // Rooted at the workspace root rather than the process cwd, so the
+        // rules apply to entries below it.
+        let (global, _) = GitignoreBuilder::new(root).build_global();
+        matchers.push(global);
+        add_file(&mut matchers, root, &root.join(".git/info/exclude"));
+
+        let mut current = root.to_path_buf();
+        add_dir(&mut matchers, &current);
+        if let Ok(relative) = dir.strip_prefix(root) {
+            for component in relative.components() {
+                current.push(component);
+                add_dir(&mut matchers, &current);
+            }
+        }
+        Self { matchers }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for matcher in self.matchers.iter().rev() {
+            if !path.starts_with(matcher.path()) {
+                continue;
+            }
+            match matcher.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+}
+
+/// `.ignore` takes precedence over `.gitignore`, as in project search.
+fn add_dir(matchers: &mut Vec<Gitignore>, dir: &Path) {
+    add_file(matchers, dir, &dir.join(".gitignore"));
+    add_file(matchers, dir, &dir.join(".ignore"));
+}
+
+fn add_file(matchers: &mut Vec<Gitignore>, dir: &Path, file: &Path) {
+    if !file.is_file() {
+        return;
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    builder.add(file);
+    if let Ok(matcher) = builder.build() {
+        if !matcher.is_empty() {
+            matchers.push(matcher);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::{
+    cmp::Ordering,
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+pub mod ignore_rules;
+pub mod types;
+
+use ignore_rules::IgnoreRules;
+use types::{DirectoryPage, FileEntry};
+
+/// Depth of the eager tree returned by `read_workspace`.
+pub const MAX_TREE_DEPTH: usize = 4;
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// Lists one page of `dir`. Only the entries of the page are stat'ed, so
+/// huge directories stay cheap to open.
+pub fn read_directory(
+    root: &Path,
+    dir: &Path,
+    offset: usize,
+    limit: usize,
+) -> Result<DirectoryPage, String> {
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", dir.display()));
+    }
+    let listed = list_sorted(dir)?;
+    let total = listed.len();
+    let rules = IgnoreRules::for_dir(root, dir);
+    let entries = listed
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .filter_map(|entry| build_file_entry(&entry.path, root, &rules, 0).ok())
+        .collect();
+    let end = offset.saturating_add(limit);
+
+    Ok(DirectoryPage {
+        path: entry_id(dir, root),
+        entries,
+        offset,
+        total,
+        next_offset: (end < total).then_some(end),
+    })
+}
+
+/// Reads the tree below `root` eagerly, `depth` levels deep.
+pub fn read_tree(root: &Path, depth: usize) -> Result<Vec<FileEntry>, String> {
+    read_entries(root, root, depth)
+}
+
+fn metadata_timestamp(metadata: &fs::Metadata) -> Option<i64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs() as i64)
+}
+
+/// Root-relative, `/`-separated id; the root itself is `.`.
+fn entry_id(path: &Path, root: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    if relative.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        relative.to_string_lossy().replace('\\', "/")
+    }
+}
+
+fn build_file_entry(
+    path: &Path,
+    root: &Path,
+    rules: &IgnoreRules,
+    depth: usize,
+) -> Result<FileEntry, String> {
+    let link = fs::symlink_metadata(path)
+        .map_err(|e| format!("Failed to stat {}: {e}", path.display()))?;
+    let symlink_target = if link.file_type().is_symlink() {
+        fs::read_link(path)
+            .ok()
+            .map(|target| target.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    // Follow symlinks for type and size; a dangling link keeps its own.
+    let metadata = fs::metadata(path).unwrap_or(link);
+    let is_dir = metadata.is_dir();
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string());
+    let node_type = if is_dir { "dir" } else { "file" }.to_string();
+    let children = if is_dir && depth > 0 {
+        Some(read_entries(path, root, depth - 1)?)
+    } else {
+        None
+    };
+    let has_children = match &children {
+        Some(children) => !children.is_empty(),
+        None => is_dir && fs::read_dir(path).is_ok_and(|mut it| it.next().is_some()),
+    };
+    Ok(FileEntry {
+        id: entry_id(path, root),
+        name,
+        path: path.to_string_lossy().to_string(),
+        node_type,
+        size: if metadata.is_file() {
+            Some(metadata.len())
+        } else {
+            None
+        },
+        modified: metadata_timestamp(&metadata),
+        children,
+        has_children,
+        symlink_target,
+        ignored: rules.is_ignored(path, is_dir),
+    })
+}
+
+fn read_entries(dir: &Path, root: &Path, depth: usize) -> Result<Vec<FileEntry>, String> {
+    // If we fail to read the directory (e.g. Access Denied), just return empty list
+    // instead of failing the whole operation.
+    let Ok(listed) = list_sorted(dir) else {
+        return Ok(Vec::new());
+    };
+    let rules = IgnoreRules::for_dir(root, dir);
+    // Skip entries that fail to build (e.g. metadata access denied).
+    Ok(listed
+        .iter()
+        .filter_map(|entry| build_file_entry(&entry.path, root, &rules, depth).ok())
+        .collect())
+}
+
+struct Listed {
+    path: PathBuf,
+    name: String,
+    is_dir: bool,
+}
+
+/// Reads the names in `dir`, directories first, then by case-insensitive
+/// name. Only symlinks need an extra stat to learn what they point to.
+fn list_sorted(dir: &Path) -> Result<Vec<Listed>, String> {
+    let iter = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+    let mut listed: Vec<Listed> = iter
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            let is_dir = match entry.file_type() {
+                Ok(t) if t.is_symlink() => path.is_dir(),
+                Ok(t) => t.is_dir(),
+                Err(_) => false,
+            };
+            Listed {
+                name: entry.file_name().to_string_lossy().to_lowercase(),
+                path,
+                is_dir,
+            }
+        })
+        .collect();
+    listed.sort_by(|a, b| match (a.is_dir, b.is_dir) {
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        _ => a.name.cmp(&b.name),
+    });
+    Ok(listed)
+}
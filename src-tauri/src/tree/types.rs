@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub size: Option<u64>,
+    pub modified: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<FileEntry>>,
+    /// Whether a directory has any entries, known without listing them.
+    /// Always `false` for files.
+    #[serde(default)]
+    pub has_children: bool,
+    /// Where the entry points to when it is a symlink. `type` describes the
+    /// target; a dangling link is reported as a file.
+    #[serde(default)]
+    pub symlink_target: Option<String>,
+    /// Excluded by `.gitignore`/`.ignore` rules or the git excludes files.
+    #[serde(default)]
+    pub ignored: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReadDirectoryRequest {
+    pub root: String,
+    /// Directory to list, relative to `root` or absolute. Defaults to `root`.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Index of the first entry returned.
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of entries returned. Defaults to `DEFAULT_PAGE_SIZE`.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// One page of a directory listing. Entries are sorted directories first,
+/// then case-insensitively by name, so pages line up between calls.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryPage {
+    pub path: String,
+    pub entries: Vec<FileEntry>,
+    pub offset: usize,
+    /// Number of entries in the whole directory.
+    pub total: usize,
+    /// Offset of the next page, if any.
+    pub next_offset: Option<usize>,
+}
@@ -0,0 +1,189 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+use notify::{
+    event::Event, recommended_watcher, Config, ErrorKind, EventHandler, PollWatcher,
+    RecommendedWatcher, RecursiveMode, Watcher,
+};
+
+use crate::watcher::{
+    fs_type,
+    types::{WatchMode, WatchPreference, WatchedRoot, WatcherConfig},
+};
+
+/// What the service thread receives.
+pub enum Message {
+    Event(notify::Result<Event>),
+    /// Sent once the watchers are gone: flush and end.
+    Stop,
+}
+
+/// Forwards notify's events into the service channel.
+struct Forward(Sender<Message>);
+
+impl EventHandler for Forward {
+    fn handle_event(&mut self, event: notify::Result<Event>) {
+        let _ = self.0.send(Message::Event(event));
+    }
+}
+
+/// The notify watchers behind a running service: the native one where it
+/// works, a polling one for the other roots. Both feed the same channel.
+pub struct Backends {
+    tx: Sender<Message>,
+    config: WatcherConfig,
+    native: Option<RecommendedWatcher>,
+    polling: Option<PollWatcher>,
+    roots: Vec<Root>,
+}
+
+struct Root {
+    path: PathBuf,
+    mode: WatchMode,
+    reason: Option<String>,
+}
+
+impl Backends {
+    pub fn new(tx: Sender<Message>, config: WatcherConfig) -> Self {
+        Self {
+            tx,
+            config,
+            native: None,
+            polling: None,
+            roots: Vec::new(),
+        }
+    }
+
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.roots.iter().map(|root| root.path.clone()).collect()
+    }
+
+    pub fn native_roots(&self) -> Vec<PathBuf> {
+        self.roots
+            .iter()
+            .filter(|root| root.mode == WatchMode::Native)
+            .map(|root| root.path.clone())
+            .collect()
+    }
+
+    pub fn status(&self) -> Vec<WatchedRoot> {
+        self.roots
+            .iter()
+            .map(|root| WatchedRoot {
+                path: root.path.to_string_lossy().to_string(),
+                mode: root.mode,
+                reason: root.reason.clone(),
+            })
+            .collect()
+    }
+
+    /// Watches `root` natively unless the configuration or its file system
+    /// call for polling; a root the native watcher fails on is polled too.
+    /// Returns a warning when the inotify watch limit was reached.
+    pub fn watch(&mut self, root: &Path) -> Result<Option<String>, String> {
+        let polling_reason = match self.config.mode {
+            WatchPreference::Polling => Some("polling is configured".to_string()),
+            WatchPreference::Auto => fs_type::needs_polling(root)
+                .map(|fs| format!("{fs} file systems don't report all changes")),
+            WatchPreference::Native => None,
+        };
+        let (reason, warning) = match polling_reason {
+            Some(reason) => (reason, None),
+            None => match self.watch_native(root) {
+                Ok(()) => {
+                    self.roots.push(Root {
+                        path: root.to_path_buf(),
+                        mode: WatchMode::Native,
+                        reason: None,
+                    });
+                    return Ok(None);
+                }
+                Err(error) => {
+                    let warning = matches!(error.kind, ErrorKind::MaxFilesWatch)
+                        .then(|| watch_limit_warning(root));
+                    (format!("native watcher failed: {error}"), warning)
+                }
+            },
+        };
+
+        self.watch_polling(root)?;
+        let reason = self.polling_reason(reason);
+        self.roots.push(Root {
+            path: root.to_path_buf(),
+            mode: WatchMode::Polling,
+            reason: Some(reason),
+        });
+        Ok(warning)
+    }
+
+    /// Moves a natively watched root to polling after the native watcher
+    /// failed on it at runtime. Returns whether the root was moved.
+    pub fn fall_back(&mut self, root: &Path, reason: String) -> Result<bool, String> {
+        let Some(index) = self
+            .roots
+            .iter()
+            .position(|r| r.path == root && r.mode == WatchMode::Native)
+        else {
+            return Ok(false);
+        };
+        if let Some(native) = &mut self.native {
+            let _ = native.unwatch(root);
+        }
+        self.watch_polling(root)?;
+        self.roots[index].mode = WatchMode::Polling;
+        self.roots[index].reason = Some(self.polling_reason(reason));
+        Ok(true)
+    }
+
+    fn watch_native(&mut self, root: &Path) -> notify::Result<()> {
+        let native = match &mut self.native {
+            Some(native) => native,
+            None => self
+                .native
+                .insert(recommended_watcher(Forward(self.tx.clone()))?),
+        };
+        let result = native.watch(root, RecursiveMode::Recursive);
+        if result.is_err() {
+            // A recursive watch that failed halfway keeps the watches it got.
+            let _ = native.unwatch(root);
+        }
+        result
+    }
+
+    fn watch_polling(&mut self, root: &Path) -> Result<(), String> {
+        let polling = match &mut self.polling {
+            Some(polling) => polling,
+            None => {
+                let config = Config::default().with_poll_interval(self.config.poll_interval());
+                let watcher = PollWatcher::new(Forward(self.tx.clone()), config)
+                    .map_err(|e| format!("Failed to create polling watcher: {e}"))?;
+                self.polling.insert(watcher)
+            }
+        };
+        polling
+            .watch(root, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {e}", root.display()))
+    }
+
+    /// notify's polling watcher can't skip directories, so every tick walks
+    /// and stats the whole root, `.git` and `node_modules` included. That is
+    /// spelled out in the status, as it is what makes large trees expensive.
+    fn polling_reason(&self, reason: String) -> String {
+        format!(
+            "{reason}; the whole tree, .git and node_modules included, is rescanned \
+             every {:.1} s, which costs CPU and disk time on large trees",
+            self.config.poll_interval().as_secs_f64()
+        )
+    }
+}
+
+pub fn watch_limit_warning(root: &Path) -> String {
+    format!(
+        "Ran out of inotify watches on {}, so it is polled instead. Raise \
+         fs.inotify.max_user_watches (e.g. `sysctl fs.inotify.max_user_watches=524288`) \
+         for native watching.",
+        root.display()
+    )
+}
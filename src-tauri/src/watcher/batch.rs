@@ -0,0 +1,211 @@
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    path::{Component, Path, PathBuf},
+    time::Instant,
+};
+
+use notify::event::{Event, EventKind, ModifyKind, RenameMode};
+
+use crate::watcher::{
+    types::{FileChangeBatch, RenamedPath},
+    DEBOUNCE, EXCLUDED_DIRS, MAX_BATCH_DELAY,
+};
+
+/// Accumulates raw watcher events until the batch is due. For each path
+/// only the first event matters, as it tells whether the path existed
+/// before the batch; whether it exists at the end is looked up on flush.
+/// That way a file created and deleted again in one burst, such as the temp
+/// file of an atomic save, is not reported at all.
+pub struct Coalescer {
+    roots: Vec<PathBuf>,
+    /// Whether the path existed before the batch, if the event tells.
+    existed: HashMap<PathBuf, Option<bool>>,
+    /// Paths in the order first seen, so batches are stable.
+    order: Vec<PathBuf>,
+    renames: Vec<(PathBuf, PathBuf)>,
+    git_metadata: bool,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+enum PathClass {
+    Reported,
+    GitMetadata,
+    Excluded,
+}
+
+impl Coalescer {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self {
+            roots,
+            existed: HashMap::new(),
+            order: Vec::new(),
+            renames: Vec::new(),
+            git_metadata: false,
+            first: None,
+            last: None,
+        }
+    }
+
+    pub fn push(&mut self, event: &Event) {
+        let existed = match &event.kind {
+            EventKind::Create(_) => Some(false),
+            EventKind::Remove(_) => Some(true),
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let [from, to] = event.paths.as_slice() {
+                    self.rename(from, to);
+                }
+                return;
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => Some(true),
+            // The target of a rename, or a rename the backend can't pair:
+            // the path may or may not have existed.
+            EventKind::Modify(ModifyKind::Name(_)) => None,
+            EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any) => Some(true),
+            _ => return,
+        };
+        for path in &event.paths {
+            self.note(path, existed);
+        }
+    }
+
+    /// When the pending batch should go out: after `DEBOUNCE` without
+    /// events, but no later than `MAX_BATCH_DELAY` after the first one.
+    pub fn deadline(&self) -> Option<Instant> {
+        Some((self.last? + DEBOUNCE).min(self.first? + MAX_BATCH_DELAY))
+    }
+
+    pub fn flush(&mut self) -> Option<FileChangeBatch> {
+        self.first.take()?;
+        self.last = None;
+        let existed = mem::take(&mut self.existed);
+        let order = mem::take(&mut self.order);
+        let renames = mem::take(&mut self.renames);
+
+        let mut batch = FileChangeBatch {
+            git_metadata: mem::take(&mut self.git_metadata),
+            ..FileChangeBatch::default()
+        };
+        // A rename is reported as such only when both ends agree with it;
+        // chains and temp files fall back to the per-path outcome.
+        let mut consumed = HashSet::new();
+        for (from, to) in renames {
+            if existed.get(&from) == Some(&Some(true))
+                && !exists(&from)
+                && exists(&to)
+                && !consumed.contains(&from)
+                && !consumed.contains(&to)
+            {
+                batch.renamed.push(RenamedPath {
+                    from: display(&from),
+                    to: display(&to),
+                });
+                consumed.insert(from);
+                consumed.insert(to);
+            }
+        }
+
+        let mut deleted = Vec::new();
+        for path in order {
+            if consumed.contains(&path) {
+                continue;
+            }
+            match (existed[&path], exists(&path)) {
+                (Some(false), true) => batch.created.push(display(&path)),
+                (Some(false), false) => {}
+                (_, true) => batch.changed.push(display(&path)),
+                (_, false) => deleted.push(path),
+            }
+        }
+        let deleted_set: HashSet<&Path> = deleted.iter().map(PathBuf::as_path).collect();
+        batch.deleted = deleted
+            .iter()
+            .filter(|path| !path.ancestors().skip(1).any(|a| deleted_set.contains(a)))
+            .map(|path| display(path))
+            .collect();
+
+        (!batch.is_empty()).then_some(batch)
+    }
+
+    fn rename(&mut self, from: &Path, to: &Path) {
+        let from_reported = self.note(from, Some(true));
+        let to_reported = self.note(to, None);
+        if from_reported && to_reported {
+            self.renames.push((from.to_path_buf(), to.to_path_buf()));
+        }
+    }
+
+    /// Records an event on `path`; returns whether the path is reported.
+    fn note(&mut self, path: &Path, existed: Option<bool>) -> bool {
+        match self.classify(path) {
+            PathClass::Excluded => return false,
+            PathClass::GitMetadata => {
+                self.git_metadata = true;
+                self.touch();
+                return false;
+            }
+            PathClass::Reported => {}
+        }
+        if !self.existed.contains_key(path) {
+            self.existed.insert(path.to_path_buf(), existed);
+            self.order.push(path.to_path_buf());
+        }
+        self.touch();
+        true
+    }
+
+    fn touch(&mut self) {
+        let now = Instant::now();
+        self.first.get_or_insert(now);
+        self.last = Some(now);
+    }
+
+    /// Excluded directories are matched below the watched root only, so a
+    /// root that itself lies in e.g. `node_modules` still works.
+    fn classify(&self, path: &Path) -> PathClass {
+        let relative = self
+            .roots
+            .iter()
+            .filter_map(|root| path.strip_prefix(root).ok())
+            .min_by_key(|relative| relative.components().count())
+            .unwrap_or(path);
+        let mut components = relative.components();
+        while let Some(component) = components.next() {
+            let Component::Normal(name) = component else {
+                continue;
+            };
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            if name == ".git" {
+                return if is_git_metadata(components.as_path()) {
+                    PathClass::GitMetadata
+                } else {
+                    PathClass::Excluded
+                };
+            }
+            if EXCLUDED_DIRS.contains(&name) {
+                return PathClass::Excluded;
+            }
+        }
+        PathClass::Reported
+    }
+}
+
+/// The files inside `.git` that git status depends on.
+fn is_git_metadata(inside: &Path) -> bool {
+    inside == Path::new("index")
+        || inside == Path::new("HEAD")
+        || inside == Path::new("packed-refs")
+        || inside.starts_with("refs")
+}
+
+fn exists(path: &Path) -> bool {
+    path.symlink_metadata().is_ok()
+}
+
+/// Separators are normalized to `/`, like every path sent to the frontend.
+fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
@@ -0,0 +1,153 @@
+use std::path::Path;
+
+/// File systems on which native change notifications miss changes made by
+/// other machines (network shares) or by the host of a VM or container.
+/// Names as Linux mountinfo, macOS `statfs` and Windows (lowercased) report
+/// them.
+const POLLING_FILE_SYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "afpfs",
+    "webdav",
+    "afs",
+    "ceph",
+    "glusterfs",
+    "davfs",
+    "9p",
+    "virtiofs",
+    "fakeowner",
+    "vboxsf",
+    "vmhgfs",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "fuse.s3fs",
+    "fuse.glusterfs",
+    "fuse.grpcfuse",
+    "fuse.osxfs",
+    "fuse.vmhgfs-fuse",
+    "macfuse",
+    "osxfuse",
+];
+
+/// The file system type of `path` when native watching won't see all
+/// changes there.
+pub fn needs_polling(path: &Path) -> Option<String> {
+    filesystem_type(path).filter(|fs| POLLING_FILE_SYSTEMS.contains(&fs.as_str()))
+}
+
+/// Type of the file system `path` is on, from the innermost mount point
+/// containing it in `/proc/self/mountinfo`.
+#[cfg(target_os = "linux")]
+fn filesystem_type(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").ok()?;
+    // id parent major:minor root mount-point options [optional...] - type source ...
+    mountinfo
+        .lines()
+        .filter_map(|line| {
+            let mount_point = unescape(line.split(' ').nth(4)?);
+            let (_, after) = line.split_once(" - ")?;
+            let fs_type = after.split(' ').next()?;
+            Some((mount_point, fs_type))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        // Later mounts on the same point hide earlier ones; `max_by_key`
+        // keeps the last of equals.
+        .max_by_key(|(mount_point, _)| mount_point.len())
+        .map(|(_, fs_type)| fs_type.to_string())
+}
+
+/// Type of the file system `path` is on, as `statfs` names it.
+#[cfg(target_os = "macos")]
+fn filesystem_type(path: &Path) -> Option<String> {
+    use std::{
+        ffi::{CStr, CString},
+        mem::MaybeUninit,
+        os::unix::ffi::OsStrExt,
+    };
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = MaybeUninit::<libc::statfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is only read once
+    // `statfs` reported that it filled it in.
+    let stat = unsafe {
+        if libc::statfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+    // SAFETY: `f_fstypename` is a NUL-terminated string within the array.
+    let name = unsafe { CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Type of the file system of the volume `path` is on, lowercased: `ntfs`,
+/// `refs`, `9p` for WSL shares, and so on.
+#[cfg(windows)]
+fn filesystem_type(path: &Path) -> Option<String> {
+    use std::{iter, os::windows::ffi::OsStrExt, ptr};
+    use windows_sys::Win32::Storage::FileSystem::{GetVolumeInformationW, GetVolumePathNameW};
+
+    const BUFFER_LEN: usize = 261;
+    let path: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(iter::once(0))
+        .collect();
+    let mut volume = [0u16; BUFFER_LEN];
+    let mut name = [0u16; BUFFER_LEN];
+    // SAFETY: both paths are NUL-terminated and the buffer lengths passed
+    // are the ones of the arrays.
+    let found = unsafe {
+        GetVolumePathNameW(path.as_ptr(), volume.as_mut_ptr(), BUFFER_LEN as u32) != 0
+            && GetVolumeInformationW(
+                volume.as_ptr(),
+                ptr::null_mut(),
+                0,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                name.as_mut_ptr(),
+                BUFFER_LEN as u32,
+            ) != 0
+    };
+    if !found {
+        return None;
+    }
+    let len = name.iter().position(|&c| c == 0).unwrap_or(BUFFER_LEN);
+    Some(String::from_utf16_lossy(&name[..len]).to_lowercase())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn filesystem_type(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Mount points escape space, tab, newline and backslash as `\ooo`.
+#[cfg(target_os = "linux")]
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let code = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match code {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
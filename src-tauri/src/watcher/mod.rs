@@ -0,0 +1,294 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use notify::ErrorKind;
+use tauri::{AppHandle, Emitter};
+
+pub mod backend;
+pub mod batch;
+pub mod fs_type;
+pub mod types;
+
+use backend::{watch_limit_warning, Backends, Message};
+use batch::Coalescer;
+use types::{FileChangeBatch, WatcherConfig, WatcherStatus, MIN_POLL_INTERVAL_MS};
+
+/// Quiet time after the last event before a batch goes out.
+pub const DEBOUNCE: Duration = Duration::from_millis(150);
+/// How long a continuous burst may hold a batch back.
+pub const MAX_BATCH_DELAY: Duration = Duration::from_secs(1);
+/// Directories whose contents are never reported. Inside `.git`, changes
+/// to the files git status depends on are still flagged on the batch.
+pub const EXCLUDED_DIRS: &[&str] = &[".git", ".hg", ".svn", "node_modules"];
+
+/// Receives every batch, in order, on a worker thread of its own; the
+/// subscribers (indexes, git, frontend) are wired up by the caller.
+pub type Dispatch = fn(&AppHandle, &FileChangeBatch);
+
+/// The one file watcher of the app. It watches a set of roots recursively
+/// and hands coalesced change batches to a dispatch function. notify can't
+/// skip directories while watching, so excluded paths are filtered from
+/// the events instead.
+///
+/// Roots are watched natively where that works and polled elsewhere (see
+/// `Backends`). Changes of the per-root modes are emitted as
+/// `file-watcher-status` events, problems as `file-watcher-warning`.
+#[derive(Clone, Default)]
+pub struct WatcherState {
+    running: Arc<Mutex<Option<Running>>>,
+    config: Arc<Mutex<WatcherConfig>>,
+}
+
+struct Running {
+    backends: Backends,
+    tx: Sender<Message>,
+    thread: JoinHandle<()>,
+}
+
+impl Running {
+    /// Stops the notify watchers, lets the thread send what is pending and
+    /// waits for it to end.
+    fn shutdown(self) {
+        drop(self.backends);
+        let _ = self.tx.send(Message::Stop);
+        let _ = self.thread.join();
+    }
+}
+
+impl WatcherState {
+    /// Starts watching `roots` unless the watcher already runs.
+    pub fn start(
+        &self,
+        app: &AppHandle,
+        roots: &[PathBuf],
+        dispatch: Dispatch,
+    ) -> Result<(), String> {
+        let mut running = self.running.lock().expect("file watcher poisoned");
+        if running.is_none() {
+            *running = Some(self.spawn(app, roots, dispatch)?);
+            self.emit_status(app, running.as_ref());
+        }
+        Ok(())
+    }
+
+    /// Stops the watcher. Returns once its thread has ended.
+    pub fn stop(&self, app: &AppHandle) {
+        if let Some(stopped) = self.take() {
+            stopped.shutdown();
+            self.emit_status(app, None);
+        }
+    }
+
+    /// Watches `roots` from scratch, starting the watcher if it is stopped.
+    /// If that fails, the watcher is left stopped.
+    pub fn restart(
+        &self,
+        app: &AppHandle,
+        roots: &[PathBuf],
+        dispatch: Dispatch,
+    ) -> Result<WatcherStatus, String> {
+        // The old thread is joined without the lock: it may need it itself
+        // to fall back to polling.
+        if let Some(previous) = self.take() {
+            previous.shutdown();
+        }
+        let mut running = self.running.lock().expect("file watcher poisoned");
+        let result = self
+            .spawn(app, roots, dispatch)
+            .map(|started| *running = Some(started));
+        self.emit_status(app, running.as_ref());
+        result.map(|()| self.status_of(running.as_ref()))
+    }
+
+    /// Points a running watcher at `roots`. A stopped watcher stays stopped.
+    pub fn retarget(
+        &self,
+        app: &AppHandle,
+        roots: &[PathBuf],
+        dispatch: Dispatch,
+    ) -> Result<(), String> {
+        let differs = self
+            .running
+            .lock()
+            .expect("file watcher poisoned")
+            .as_ref()
+            .is_some_and(|current| current.backends.roots() != outermost(roots));
+        if differs {
+            self.restart(app, roots, dispatch)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the configuration; a running watcher is restarted with it.
+    /// The poll interval is raised to the minimum so the status shows the
+    /// one in effect.
+    pub fn configure(
+        &self,
+        app: &AppHandle,
+        mut config: WatcherConfig,
+        roots: &[PathBuf],
+        dispatch: Dispatch,
+    ) -> Result<WatcherStatus, String> {
+        config.poll_interval_ms = config.poll_interval_ms.max(MIN_POLL_INTERVAL_MS);
+        *self.config.lock().expect("watcher config poisoned") = config;
+        if self.status().running {
+            return self.restart(app, roots, dispatch);
+        }
+        let status = self.status();
+        let _ = app.emit("file-watcher-status", &status);
+        Ok(status)
+    }
+
+    pub fn status(&self) -> WatcherStatus {
+        self.status_of(self.running.lock().expect("file watcher poisoned").as_ref())
+    }
+
+    fn take(&self) -> Option<Running> {
+        self.running.lock().expect("file watcher poisoned").take()
+    }
+
+    fn status_of(&self, running: Option<&Running>) -> WatcherStatus {
+        WatcherStatus {
+            running: running.is_some(),
+            roots: running.map_or_else(Vec::new, |r| r.backends.status()),
+            config: self.config.lock().expect("watcher config poisoned").clone(),
+        }
+    }
+
+    fn emit_status(&self, app: &AppHandle, running: Option<&Running>) {
+        let _ = app.emit("file-watcher-status", self.status_of(running));
+    }
+
+    fn spawn(
+        &self,
+        app: &AppHandle,
+        roots: &[PathBuf],
+        dispatch: Dispatch,
+    ) -> Result<Running, String> {
+        let roots = outermost(roots);
+        let config = self.config.lock().expect("watcher config poisoned").clone();
+        let (tx, rx) = mpsc::channel::<Message>();
+        let mut backends = Backends::new(tx.clone(), config);
+        for root in &roots {
+            if let Some(warning) = backends.watch(root)? {
+                let _ = app.emit("file-watcher-warning", warning);
+            }
+        }
+
+        // Subscribers run here, so a slow one (symbol parsing) doesn't hold
+        // up coalescing. Not joined on shutdown: it ends by itself after the
+        // batches already sent.
+        let (batches, received) = mpsc::channel::<FileChangeBatch>();
+        let worker_app = app.clone();
+        thread::spawn(move || {
+            for batch in received {
+                dispatch(&worker_app, &batch);
+            }
+        });
+
+        let state = self.clone();
+        let app = app.clone();
+        let coalescer = Coalescer::new(roots);
+        let thread = thread::spawn(move || state.run(&app, rx, coalescer, batches));
+        Ok(Running {
+            backends,
+            tx,
+            thread,
+        })
+    }
+
+    /// Batches events until told to stop, then sends what is pending to the
+    /// dispatch worker. A closed channel ends the thread as well.
+    fn run(
+        &self,
+        app: &AppHandle,
+        rx: mpsc::Receiver<Message>,
+        mut pending: Coalescer,
+        batches: Sender<FileChangeBatch>,
+    ) {
+        loop {
+            let received = match pending.deadline() {
+                Some(deadline) => {
+                    rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(Message::Event(Ok(event))) => pending.push(&event),
+                Ok(Message::Event(Err(error))) => self.native_failed(app, &error),
+                Err(RecvTimeoutError::Timeout) => {}
+                Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if pending
+                .deadline()
+                .is_some_and(|deadline| deadline <= Instant::now())
+            {
+                if let Some(batch) = pending.flush() {
+                    let _ = batches.send(batch);
+                }
+            }
+        }
+        if let Some(batch) = pending.flush() {
+            let _ = batches.send(batch);
+        }
+    }
+
+    /// Out of inotify watches, the native watcher misses everything in new
+    /// directories, so the affected roots move to polling.
+    fn native_failed(&self, app: &AppHandle, error: &notify::Error) {
+        if !matches!(error.kind, ErrorKind::MaxFilesWatch) {
+            return;
+        }
+        let mut running = self.running.lock().expect("file watcher poisoned");
+        let Some(current) = running.as_mut() else {
+            return;
+        };
+        let affected: Vec<PathBuf> = current
+            .backends
+            .native_roots()
+            .into_iter()
+            .filter(|root| {
+                error.paths.is_empty() || error.paths.iter().any(|p| p.starts_with(root))
+            })
+            .collect();
+        let mut changed = false;
+        for root in affected {
+            let warning = watch_limit_warning(&root);
+            match current.backends.fall_back(&root, warning.clone()) {
+                Ok(true) => {
+                    changed = true;
+                    let _ = app.emit("file-watcher-warning", warning);
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    let _ = app.emit("file-watcher-warning", e);
+                }
+            }
+        }
+        if changed {
+            self.emit_status(app, running.as_ref());
+        }
+    }
+}
+
+/// Drops roots inside other roots, which their ancestor's recursive watch
+/// already covers; watching both would report every change twice.
+fn outermost(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut result: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        let covered = roots
+            .iter()
+            .any(|other| other != root && root.starts_with(other));
+        if !covered && !result.contains(root) {
+            result.push(root.clone());
+        }
+    }
+    result
+}
@@ -0,0 +1,124 @@
+use std::{path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// File system changes coalesced over a short window, emitted as one
+/// `file-changes` event. Paths are absolute.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FileChangeBatch {
+    pub created: Vec<String>,
+    /// Deleted paths; entries below a deleted directory are left out.
+    pub deleted: Vec<String>,
+    pub renamed: Vec<RenamedPath>,
+    /// Contents changed, or the file was replaced by another one renamed
+    /// onto it (as atomic saves do).
+    pub changed: Vec<String>,
+    /// Git metadata (index, HEAD, refs) changed. Paths inside `.git` are
+    /// never reported themselves.
+    pub git_metadata: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamedPath {
+    pub from: String,
+    pub to: String,
+}
+
+impl FileChangeBatch {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty()
+            && self.deleted.is_empty()
+            && self.renamed.is_empty()
+            && self.changed.is_empty()
+            && !self.git_metadata
+    }
+
+    /// Paths that appeared, went away or moved, renames by both names.
+    pub fn structural_paths(&self) -> Vec<PathBuf> {
+        self.created
+            .iter()
+            .chain(&self.deleted)
+            .chain(self.renamed.iter().flat_map(|r| [&r.from, &r.to]))
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    pub fn changed_paths(&self) -> Vec<PathBuf> {
+        self.changed.iter().map(PathBuf::from).collect()
+    }
+
+    /// Every path the batch mentions.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        let mut paths = self.structural_paths();
+        paths.extend(self.changed_paths());
+        paths
+    }
+}
+
+/// How a root is actually watched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchMode {
+    Native,
+    Polling,
+}
+
+/// How roots should be watched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchPreference {
+    /// Native, except on file systems known not to report all changes.
+    #[default]
+    Auto,
+    /// Native everywhere; roots it fails on are still polled.
+    Native,
+    Polling,
+}
+
+/// Shorter intervals would have the polling watcher rescan the roots almost
+/// back to back; `0` would never let it rest.
+pub const MIN_POLL_INTERVAL_MS: u64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherConfig {
+    #[serde(default)]
+    pub mode: WatchPreference,
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+fn default_poll_interval_ms() -> u64 {
+    5000
+}
+
+impl WatcherConfig {
+    /// The configured interval, raised to `MIN_POLL_INTERVAL_MS`.
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms.max(MIN_POLL_INTERVAL_MS))
+    }
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            mode: WatchPreference::default(),
+            poll_interval_ms: default_poll_interval_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchedRoot {
+    pub path: String,
+    pub mode: WatchMode,
+    /// Why the root is polled.
+    pub reason: Option<String>,
+}
+
+/// Payload of `file_watcher_status` and the `file-watcher-status` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct WatcherStatus {
+    pub running: bool,
+    pub roots: Vec<WatchedRoot>,
+    pub config: WatcherConfig,
+}
@@ -0,0 +1,318 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+pub mod policy;
+pub mod types;
+
+use policy::{Access, PathPolicy};
+use types::WorkspaceRoot;
+
+/// The root folders of the open workspace. The first root is the primary
+/// one: relative paths from the frontend are resolved against it. There are
+/// no roots at startup, so file commands are denied until a folder is opened.
+#[derive(Clone, Default)]
+pub struct WorkspaceState {
+    roots: Arc<Mutex<Vec<PathBuf>>>,
+    /// Folders the user picked in the native folder dialog. Only these can
+    /// become roots, so the webview can't open an arbitrary directory.
+    picked: Arc<Mutex<Vec<PathBuf>>>,
+    policy: Arc<Mutex<PathPolicy>>,
+}
+
+impl WorkspaceState {
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.roots.lock().expect("workspace roots poisoned").clone()
+    }
+
+    pub fn primary_root(&self) -> Option<PathBuf> {
+        self.roots
+            .lock()
+            .expect("workspace roots poisoned")
+            .first()
+            .cloned()
+    }
+
+    pub fn info(&self) -> Vec<WorkspaceRoot> {
+        self.roots
+            .lock()
+            .expect("workspace roots poisoned")
+            .iter()
+            .map(|root| to_info(root))
+            .collect()
+    }
+
+    /// Records a folder the user picked in the native dialog, so it may
+    /// become a root.
+    pub fn record_pick(&self, folder: &Path) -> Result<(), String> {
+        let canonical = validate_root(folder)?;
+        let mut picked = self.picked.lock().expect("picked folders poisoned");
+        if !picked.contains(&canonical) {
+            picked.push(canonical);
+        }
+        Ok(())
+    }
+
+    /// Replaces all roots. Each one must have been picked in the folder
+    /// dialog. Returns whether anything changed.
+    pub fn set_roots<P: AsRef<Path>>(&self, roots: &[P]) -> Result<bool, String> {
+        let mut validated: Vec<PathBuf> = Vec::with_capacity(roots.len());
+        for root in roots {
+            let root = self.picked_root(root.as_ref())?;
+            if !validated.contains(&root) {
+                validated.push(root);
+            }
+        }
+        let mut current = self.roots.lock().expect("workspace roots poisoned");
+        if *current == validated {
+            return Ok(false);
+        }
+        *current = validated;
+        Ok(true)
+    }
+
+    /// Appends a root picked in the folder dialog. Returns whether it was new.
+    pub fn add_root(&self, root: &Path) -> Result<bool, String> {
+        let root = self.picked_root(root)?;
+        let mut current = self.roots.lock().expect("workspace roots poisoned");
+        if current.contains(&root) {
+            return Ok(false);
+        }
+        current.push(root);
+        Ok(true)
+    }
+
+    /// Picks are compared canonically, but the root keeps the path it was
+    /// given: canonical paths on Windows carry a `\\?\` prefix.
+    fn picked_root(&self, root: &Path) -> Result<PathBuf, String> {
+        let canonical = validate_root(root)?;
+        let picked = self.picked.lock().expect("picked folders poisoned");
+        if !picked.contains(&canonical) {
+            return Err(format!(
+                "Workspace root {} was not picked in the folder dialog",
+                root.display()
+            ));
+        }
+        Ok(root.to_path_buf())
+    }
+
+    /// Removes a root. Returns whether it was present.
+    pub fn remove_root(&self, root: &str) -> bool {
+        let root = PathBuf::from(root);
+        let mut current = self.roots.lock().expect("workspace roots poisoned");
+        let before = current.len();
+        current.retain(|r| *r != root);
+        current.len() != before
+    }
+
+    /// Resolves a path from the frontend. Absolute paths are taken as they
+    /// are, relative ones are joined onto the primary root.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, String> {
+        let candidate = PathBuf::from(path);
+        if candidate.is_absolute() {
+            return Ok(candidate);
+        }
+        let root = self
+            .primary_root()
+            .ok_or_else(|| format!("Cannot resolve {path}: no workspace folder is open"))?;
+        if path.is_empty() || path == "." {
+            return Ok(root);
+        }
+        Ok(root.join(candidate))
+    }
+}
+
+impl WorkspaceState {
+    /// Resolves a path like `resolve` and checks it against the path policy.
+    /// File commands go through here so the webview can't reach outside the
+    /// workspace without the user's consent.
+    pub fn resolve_checked(&self, path: &str, access: Access) -> Result<PathBuf, String> {
+        let resolved = self.resolve(path)?;
+        self.check(&resolved, access)?;
+        Ok(resolved)
+    }
+
+    pub fn check(&self, path: &Path, access: Access) -> Result<(), String> {
+        let roots = self.roots();
+        self.policy
+            .lock()
+            .expect("path policy poisoned")
+            .check(&roots, path, access)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Allows access below `dir` without confirmation.
+    pub fn allow(&self, dir: &Path) {
+        self.policy.lock().expect("path policy poisoned").allow(dir);
+    }
+
+    /// Allows access below `path` after the user confirmed it.
+    pub fn grant(&self, path: &Path) -> Result<(), String> {
+        self.policy
+            .lock()
+            .expect("path policy poisoned")
+            .grant(path)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Returns the canonical form of a would-be root. The filesystem root is
+/// refused: opening it would put every file on the machine in reach.
+fn validate_root(root: &Path) -> Result<PathBuf, String> {
+    if !root.is_absolute() {
+        return Err(format!(
+            "Workspace root {} must be an absolute path",
+            root.display()
+        ));
+    }
+    if !root.is_dir() {
+        return Err(format!(
+            "Workspace root {} is not a directory",
+            root.display()
+        ));
+    }
+    let canonical = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {e}", root.display()))?;
+    if canonical.parent().is_none() {
+        return Err(format!(
+            "The filesystem root {} can't be a workspace root",
+            root.display()
+        ));
+    }
+    Ok(canonical)
+}
+
+fn to_info(root: &Path) -> WorkspaceRoot {
+    let path = root.to_string_lossy().replace('\\', "/");
+    WorkspaceRoot {
+        name: root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.clone()),
+        path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A fresh directory under the system temp dir, unique per test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("nova-workspace-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn opened(root: &Path) -> WorkspaceState {
+        let workspace = WorkspaceState::default();
+        workspace.record_pick(root).unwrap();
+        workspace.set_roots(&[root]).unwrap();
+        workspace
+    }
+
+    #[test]
+    fn denies_everything_until_a_folder_is_opened() {
+        let dir = scratch("empty");
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        let workspace = WorkspaceState::default();
+
+        assert!(workspace.roots().is_empty());
+        assert!(workspace.resolve_checked("a.txt", Access::Content).is_err());
+        let absolute = dir.join("a.txt");
+        assert!(workspace
+            .resolve_checked(absolute.to_str().unwrap(), Access::Content)
+            .is_err());
+    }
+
+    #[test]
+    fn only_picked_folders_become_roots() {
+        let dir = scratch("picked");
+        let workspace = WorkspaceState::default();
+
+        assert!(workspace.set_roots(&[&dir]).is_err());
+        assert!(workspace.add_root(&dir).is_err());
+
+        workspace.record_pick(&dir).unwrap();
+        assert!(workspace.set_roots(&[&dir]).unwrap());
+        assert_eq!(workspace.roots(), vec![dir.clone()]);
+        assert!(workspace.set_roots::<PathBuf>(&[]).unwrap());
+        assert!(workspace.add_root(&dir).unwrap());
+    }
+
+    #[test]
+    fn refuses_the_filesystem_root_and_relative_paths() {
+        let workspace = WorkspaceState::default();
+        let fs_root = std::env::temp_dir()
+            .ancestors()
+            .last()
+            .unwrap()
+            .to_path_buf();
+
+        assert!(workspace.record_pick(&fs_root).is_err());
+        assert!(workspace.record_pick(Path::new("relative")).is_err());
+        assert!(workspace.set_roots(&[&fs_root]).is_err());
+    }
+
+    #[test]
+    fn resolves_inside_the_root_and_rejects_escapes() {
+        let dir = scratch("policy");
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(dir.join("secret.txt"), "secret").unwrap();
+        let workspace = opened(&root);
+
+        assert_eq!(
+            workspace.resolve_checked("src/new.rs", Access::Content),
+            Ok(root.join("src/new.rs"))
+        );
+        assert!(workspace
+            .resolve_checked("../secret.txt", Access::Content)
+            .unwrap_err()
+            .starts_with("outside-workspace: "));
+        assert!(workspace
+            .resolve_checked("missing/../../secret.txt", Access::Content)
+            .is_err());
+        // Renaming or deleting the root itself is refused.
+        assert!(workspace
+            .resolve_checked(root.to_str().unwrap(), Access::Entry)
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_pointing_out_of_the_root() {
+        let dir = scratch("symlink");
+        let root = dir.join("root");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(dir.join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(dir.join("secret.txt"), root.join("link.txt")).unwrap();
+        let workspace = opened(&root);
+
+        assert!(workspace
+            .resolve_checked("link.txt", Access::Content)
+            .is_err());
+        // The link itself lives in the root, so it may be renamed or deleted.
+        assert!(workspace.resolve_checked("link.txt", Access::Entry).is_ok());
+    }
+
+    #[test]
+    fn granted_paths_are_allowed() {
+        let dir = scratch("grant");
+        let root = dir.join("root");
+        let other = dir.join("other");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        let workspace = opened(&root);
+        let outside = other.join("notes.md");
+
+        assert!(workspace.check(&outside, Access::Content).is_err());
+        workspace.grant(&other).unwrap();
+        assert!(workspace.check(&outside, Access::Content).is_ok());
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+/// What a command is about to do with a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Reads or writes the file contents; symlinks are followed.
+    Content,
+    /// Creates, renames or deletes the entry itself. A symlink in last
+    /// position is not followed, since only the link is affected.
+    Entry,
+}
+
+#[derive(Debug)]
+pub enum PolicyError {
+    /// The path is outside every root and allowed location. The frontend
+    /// may ask the user via `workspace_request_access` and retry.
+    OutsideWorkspace(PathBuf),
+    /// `..` below a path that does not exist yet.
+    Traversal(PathBuf),
+    /// Renaming or deleting a workspace root or one of its ancestors.
+    ProtectedRoot(PathBuf),
+    Io(String),
+}
+
+/// The prefix is stable so the frontend can start the confirmation flow.
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::OutsideWorkspace(path) => {
+                write!(f, "outside-workspace: {}", path.display())
+            }
+            PolicyError::Traversal(path) => {
+                write!(f, "Path traversal is not allowed: {}", path.display())
+            }
+            PolicyError::ProtectedRoot(path) => {
+                write!(f, "{} contains a workspace root", path.display())
+            }
+            PolicyError::Io(msg) => f.write_str(msg),
+        }
+    }
+}
+
+/// Locations commands may touch: the workspace roots, fixed allowlisted
+/// directories such as the app config dir, and paths the user confirmed.
+/// All checks run on canonical paths, so symlinks pointing out of a root
+/// and `..` segments are caught.
+#[derive(Default)]
+pub struct PathPolicy {
+    allowlist: Vec<PathBuf>,
+    granted: Vec<PathBuf>,
+}
+
+impl PathPolicy {
+    pub fn allow(&mut self, dir: &Path) {
+        if let Ok(dir) = dir.canonicalize() {
+            if !self.allowlist.contains(&dir) {
+                self.allowlist.push(dir);
+            }
+        }
+    }
+
+    /// Records the user's consent for `path` and everything below it, for
+    /// the rest of the session.
+    pub fn grant(&mut self, path: &Path) -> Result<(), PolicyError> {
+        let canonical = canonicalize_lenient(path)?;
+        if !self.granted.contains(&canonical) {
+            self.granted.push(canonical);
+        }
+        Ok(())
+    }
+
+    pub fn check(&self, roots: &[PathBuf], path: &Path, access: Access) -> Result<(), PolicyError> {
+        let canonical = match access {
+            Access::Content => canonicalize_lenient(path)?,
+            Access::Entry => match (path.parent(), path.file_name()) {
+                (Some(parent), Some(name)) => canonicalize_lenient(parent)?.join(name),
+                (Some(_), None) => return Err(PolicyError::Traversal(path.to_path_buf())),
+                (None, _) => return Err(PolicyError::ProtectedRoot(path.to_path_buf())),
+            },
+        };
+        let roots: Vec<PathBuf> = roots.iter().filter_map(|r| r.canonicalize().ok()).collect();
+
+        if access == Access::Entry && roots.iter().any(|root| root.starts_with(&canonical)) {
+            return Err(PolicyError::ProtectedRoot(path.to_path_buf()));
+        }
+        let allowed = roots
+            .iter()
+            .chain(&self.allowlist)
+            .chain(&self.granted)
+            .any(|dir| canonical.starts_with(dir));
+        if allowed {
+            Ok(())
+        } else {
+            Err(PolicyError::OutsideWorkspace(path.to_path_buf()))
+        }
+    }
+}
+
+/// Canonicalizes `path`, which may not exist yet: the deepest existing
+/// ancestor is canonicalized and the missing components appended. Those
+/// may not contain `..`, as it can't be resolved without the directories.
+fn canonicalize_lenient(path: &Path) -> Result<PathBuf, PolicyError> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        match existing.canonicalize() {
+            Ok(canonical) => {
+                let mut resolved = canonical;
+                for component in missing.into_iter().rev() {
+                    match component {
+                        Component::Normal(name) => resolved.push(name),
+                        Component::CurDir => {}
+                        _ => return Err(PolicyError::Traversal(path.to_path_buf())),
+                    }
+                }
+                return Ok(resolved);
+            }
+            Err(e) => {
+                let (Some(parent), Some(last)) =
+                    (existing.parent(), existing.components().next_back())
+                else {
+                    return Err(PolicyError::Io(format!(
+                        "Failed to resolve {}: {e}",
+                        path.display()
+                    )));
+                };
+                missing.push(last);
+                existing = parent;
+            }
+        }
+    }
+}
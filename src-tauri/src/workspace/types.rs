@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+/// One root folder of the workspace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceRoot {
+    pub path: String,
+    pub name: String,
+}
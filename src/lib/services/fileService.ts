@@ -5,35 +5,285 @@
 // заменить моковую логику при переходе к реальному файловому backend.
 // -----------------------------------------------------------------------------
 
-import type { FileNode } from '../types/fileNode';
+import type { DirectoryPage, FileNode } from '../types/fileNode';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 
 let workspaceRoot = '.';
-const FILE_CHANGED_EVENT = 'file-changed';
+const FILE_CHANGES_EVENT = 'file-changes';
+/** Префикс ошибки backend для путей вне workspace (см. `PolicyError`). */
+const OUTSIDE_WORKSPACE_PREFIX = 'outside-workspace: ';
+
+/**
+ * Выполняет команду; если backend отклонил путь вне workspace, просит
+ * пользователя подтвердить доступ через нативный диалог и повторяет вызов.
+ */
+const withAccess = async <T>(path: string, run: () => Promise<T>): Promise<T> => {
+  try {
+    return await run();
+  } catch (error) {
+    // write_file возвращает структурированную ошибку, остальные команды — строку.
+    const message =
+      typeof error === 'string' ? error : (error as { message?: string } | null)?.message;
+    if (typeof message !== 'string' || !message.startsWith(OUTSIDE_WORKSPACE_PREFIX)) {
+      throw error;
+    }
+    const granted = await invoke<boolean>('workspace_request_access', { path });
+    if (!granted) {
+      throw error;
+    }
+    return run();
+  }
+};
+
+export interface QuickOpenMatch {
+  /** Путь относительно корня workspace, через `/`. */
+  path: string;
+  name: string;
+  score: number;
+  /** Совпавшие позиции в `path` (UTF-16 offsets) для подсветки. */
+  positions: number[];
+}
+
+export interface QuickOpenIndexInfo {
+  root: string;
+  file_count: number;
+}
+
+export interface TextFileContent {
+  content: string;
+  /** WHATWG-имя кодировки, например `UTF-8` или `windows-1251`. */
+  encoding: string;
+  bom: boolean;
+  /** При декодировании были заменены невалидные байты (вероятно, не та кодировка). */
+  had_errors: boolean;
+  /** Преобладающий перевод строки; `null`, если переводов строк нет. */
+  eol: LineEnding | null;
+  mixed_eol: boolean;
+  final_newline: boolean;
+  /** Версия файла на диске; передаётся обратно как `expected_version` при сохранении. */
+  version: FileVersion;
+}
+
+export interface FileVersion {
+  /** mtime в миллисекундах. */
+  mtime: number | null;
+  size: number;
+  /** SHA-256 содержимого. */
+  hash: string;
+}
+
+/** Ошибка `write_file`; `conflict` — файл изменился на диске после загрузки. */
+export type WriteFileError =
+  | { kind: 'conflict'; path: string; expected: FileVersion; actual: FileVersion | null }
+  | { kind: 'failed'; message: string };
+
+export const isWriteConflict = (
+  error: unknown,
+): error is Extract<WriteFileError, { kind: 'conflict' }> =>
+  typeof error === 'object' && error !== null && (error as WriteFileError).kind === 'conflict';
+
+export type LineEnding = 'lf' | 'crlf';
+
+export interface WriteOptions {
+  /** Кодировка сохранения; по умолчанию — текущая кодировка файла на диске. */
+  encoding?: string;
+  /** Писать ли BOM; по умолчанию сохраняется текущий BOM файла. */
+  bom?: boolean;
+  /** `keep` сохраняет переводы строк файла на диске (по умолчанию). */
+  line_ending?: 'keep' | LineEnding;
+  trim_trailing_whitespace?: boolean;
+  insert_final_newline?: boolean;
+  /** Версия, с которой работал редактор; при несовпадении — ошибка `conflict`. */
+  expected_version?: FileVersion;
+}
+
+/** Как UI открывает файл: целиком в редакторе, в read-only viewer или в hex. */
+export type OpenMode = 'editor' | 'viewer' | 'hex';
+
+export interface FileInfo {
+  path: string;
+  size: number;
+  modified: number | null;
+  kind: 'text' | 'binary';
+  encoding: string | null;
+  mode: OpenMode;
+}
+
+export interface TextChunk {
+  offset: number;
+  /** Смещение следующего куска; `null` в конце файла. */
+  next_offset: number | null;
+  size: number;
+  data: string;
+  encoding: string;
+}
+
+export interface HexDump {
+  offset: number;
+  size: number;
+  rows: { offset: number; hex: string; ascii: string }[];
+  next_offset: number | null;
+}
+
+export interface WorkspaceRoot {
+  path: string;
+  name: string;
+}
+
+/** Батч изменений от общего watcher backend'а (событие `file-changes`). */
+export interface FileChangeBatch {
+  created: string[];
+  /** Удалённые пути; содержимое удалённой директории не перечисляется. */
+  deleted: string[];
+  renamed: { from: string; to: string }[];
+  /** Изменилось содержимое или файл заменён переименованием (атомарное сохранение). */
+  changed: string[];
+  /** Изменились index/HEAD/refs в `.git`. */
+  git_metadata: boolean;
+}
+
+export type WatchMode = 'native' | 'polling';
+
+export interface WatcherConfig {
+  /** auto: polling для сетевых/контейнерных ФС, native иначе. */
+  mode: 'auto' | 'native' | 'polling';
+  poll_interval_ms: number;
+}
+
+export interface WatcherStatus {
+  running: boolean;
+  roots: { path: string; mode: WatchMode; reason: string | null }[];
+  config: WatcherConfig;
+}
+
+/** Что делать, если цель копирования/перемещения уже существует. */
+export type ConflictPolicy = 'overwrite' | 'skip' | 'rename';
+
+export interface TransferOptions {
+  /** По умолчанию `rename`: `file (1).rs`. */
+  conflict?: ConflictPolicy;
+  /** Возвращается в событиях прогресса, чтобы отличать операции. */
+  operationId?: string;
+}
+
+export interface TransferredPath {
+  source: string;
+  /** Куда попал источник; `null`, если он пропущен. */
+  target: string | null;
+}
+
+export interface TransferProgress {
+  operation_id: string | null;
+  files_done: number;
+  files_total: number;
+  bytes_done: number;
+  bytes_total: number;
+  current: string;
+}
+
+/** Структурная операция в журнале undo/redo файловых операций. */
+export interface FileOperation {
+  kind: 'create' | 'rename' | 'move' | 'copy' | 'duplicate' | 'delete' | 'trash';
+  /** Созданные, удалённые или целевые пути операции. */
+  paths: string[];
+}
+
+export interface FileJournalStatus {
+  undo: FileOperation | null;
+  redo: FileOperation | null;
+}
+
+/** Пути, появившиеся, исчезнувшие или перемещённые в батче. */
+export const structuralPaths = (batch: FileChangeBatch): string[] => [
+  ...batch.created,
+  ...batch.deleted,
+  ...batch.renamed.flatMap((r) => [r.from, r.to]),
+];
 
 export interface FileService {
   readFile(fileId: string): Promise<string>;
-  writeFile(fileId: string, content: string): Promise<void>;
+  /** Читает файл с определением кодировки; `encoding` — «reopen with encoding». */
+  readTextFile(fileId: string, encoding?: string): Promise<TextFileContent>;
+  /** `options.encoding` — «save with encoding». Возвращает новую версию файла. */
+  writeFile(fileId: string, content: string, options?: WriteOptions): Promise<FileVersion>;
   listWorkspaceFiles(rootOverride?: string): Promise<FileNode[]>;
+  listDirectory(path?: string, offset?: number, limit?: number): Promise<DirectoryPage>;
+  /** Вызывается для каждого структурного пути (создание/удаление/переименование). */
   onFileChange(cb: (fileId: string) => void): Promise<() => void>;
+  onFileChanges(cb: (batch: FileChangeBatch) => void): Promise<() => void>;
+  statFile(path: string): Promise<FileInfo>;
+  readFileChunk(path: string, offset: number, length?: number, encoding?: string): Promise<TextChunk>;
+  readFileHex(path: string, offset: number, length?: number): Promise<HexDump>;
+  /**
+   * Потоковое чтение для viewer: куски приходят событиями `file-stream-chunk`.
+   * Следующий кусок бэкенд шлёт только после обработки предыдущих (окно
+   * из нескольких кусков), поэтому `onChunk` может вернуть Promise.
+   */
+  openFileStream(
+    path: string,
+    onChunk: (chunk: TextChunk) => void | Promise<void>,
+  ): Promise<() => void>;
   createFile(path: string): Promise<void>;
   createDirectory(path: string): Promise<void>;
   renameFile(oldPath: string, newPath: string): Promise<void>;
+  /** Копирует файлы и папки в директорию `destination`. */
+  copyPaths(sources: string[], destination: string, options?: TransferOptions): Promise<TransferredPath[]>;
+  /** Перемещает файлы и папки в `destination`, в том числе между дисками. */
+  movePaths(sources: string[], destination: string, options?: TransferOptions): Promise<TransferredPath[]>;
+  /** Копирует путь рядом с собой как `name (1).ext`; возвращает новый путь. */
+  duplicatePath(path: string): Promise<string>;
+  onTransferProgress(cb: (progress: TransferProgress) => void): Promise<() => void>;
+  /** Отменяет последнюю файловую операцию; `null`, если отменять нечего. */
+  undoFileOperation(): Promise<FileOperation | null>;
+  redoFileOperation(): Promise<FileOperation | null>;
+  /** Что отменят/повторят undo и redo — для подписей в меню. */
+  getFileJournalStatus(): Promise<FileJournalStatus>;
   deleteFile(path: string, useTrash: boolean): Promise<void>;
   revealInExplorer(path: string): Promise<void>;
   setWorkspaceRoot(root: string): void;
+  /**
+   * Открывает нативный диалог выбора папки на backend и делает её корнем
+   * (`add` — добавляет к текущим). `null`, если диалог отменён.
+   */
+  pickWorkspaceFolder(add?: boolean): Promise<WorkspaceRoot[] | null>;
+  /** Задаёт корни workspace на backend; принимаются только выбранные в диалоге папки. */
+  setWorkspaceRoots(roots: string[]): Promise<WorkspaceRoot[]>;
+  getWorkspaceRoots(): Promise<WorkspaceRoot[]>;
   getWorkspaceRoot(): string;
   startFileWatcher(): Promise<void>;
+  stopFileWatcher(): Promise<void>;
+  /** Пересоздаёт watcher на текущих корнях workspace. */
+  restartFileWatcher(): Promise<WatcherStatus>;
+  /** Следит за указанными папками вместо корней workspace. */
+  retargetFileWatcher(roots: string[]): Promise<WatcherStatus>;
+  getWatcherStatus(): Promise<WatcherStatus>;
+  configureWatcher(config: WatcherConfig): Promise<WatcherStatus>;
+  /** Статус при смене режимов корней; предупреждения (лимит inotify и т.п.). */
+  onWatcherStatus(cb: (status: WatcherStatus) => void): Promise<() => void>;
+  onWatcherWarning(cb: (message: string) => void): Promise<() => void>;
+  quickOpen(query: string, limit?: number): Promise<QuickOpenMatch[]>;
+  buildQuickOpenIndex(rebuild?: boolean): Promise<QuickOpenIndexInfo>;
 }
 
 export const fileService: FileService = {
   async readFile(fileId) {
-    return invoke<string>('read_file', { path: fileId });
+    const file = await withAccess(fileId, () =>
+      invoke<TextFileContent>('read_file', { path: fileId }),
+    );
+    return file.content;
   },
 
-  async writeFile(fileId, content) {
-    await invoke<void>('write_file', { request: { path: fileId, content } });
+  async readTextFile(fileId, encoding) {
+    return withAccess(fileId, () =>
+      invoke<TextFileContent>('read_file', { path: fileId, encoding }),
+    );
+  },
+
+  async writeFile(fileId, content, options = {}) {
+    return withAccess(fileId, () =>
+      invoke<FileVersion>('write_file', { request: { path: fileId, content, ...options } }),
+    );
   },
 
   async listWorkspaceFiles(rootOverride?: string) {
@@ -41,8 +291,81 @@ export const fileService: FileService = {
     return invoke<FileNode[]>('read_workspace', { root: targetRoot || '.' });
   },
 
+  async listDirectory(path, offset = 0, limit) {
+    return invoke<DirectoryPage>('read_directory', {
+      request: { root: workspaceRoot || '.', path, offset, limit },
+    });
+  },
+
+  async statFile(path) {
+    return withAccess(path, () => invoke<FileInfo>('stat_file', { path }));
+  },
+
+  async readFileChunk(path, offset, length, encoding) {
+    return withAccess(path, () =>
+      invoke<TextChunk>('read_file_chunk', { path, offset, length, encoding }),
+    );
+  },
+
+  async readFileHex(path, offset, length) {
+    return withAccess(path, () => invoke<HexDump>('read_file_hex', { path, offset, length }));
+  },
+
+  async openFileStream(path, onChunk) {
+    let streamId: number | null = null;
+    let ended = false;
+    const pending: { stream_id: number; chunk: TextChunk }[] = [];
+    const endedEarly = new Set<number>();
+    // Куски обрабатываются строго по очереди, даже если onChunk асинхронный.
+    let queue = Promise.resolve();
+    const deliver = (chunk: TextChunk) => {
+      queue = queue
+        .then(() => onChunk(chunk))
+        .catch((error) => console.error('Ошибка обработки куска потока:', error))
+        .finally(() => {
+          // Подтверждение открывает окно для следующего куска.
+          if (!ended) void invoke<void>('ack_file_stream', { streamId });
+        });
+    };
+    const stop = () => {
+      ended = true;
+      unlistenChunk();
+      unlistenEnd();
+    };
+    const unlistenChunk = await listen<{ stream_id: number; chunk: TextChunk }>(
+      'file-stream-chunk',
+      (event) => {
+        // Куски могут прийти раньше, чем invoke вернёт id потока.
+        if (streamId === null) pending.push(event.payload);
+        else if (event.payload.stream_id === streamId) deliver(event.payload.chunk);
+      },
+    );
+    const unlistenEnd = await listen<{ stream_id: number }>('file-stream-end', (event) => {
+      if (streamId === null) endedEarly.add(event.payload.stream_id);
+      else if (event.payload.stream_id === streamId) stop();
+    });
+    try {
+      streamId = await withAccess(path, () => invoke<number>('open_file_stream', { path }));
+    } catch (error) {
+      stop();
+      throw error;
+    }
+    const id = streamId;
+    pending.filter((p) => p.stream_id === id).forEach((p) => deliver(p.chunk));
+    if (endedEarly.has(id)) stop();
+    return () => {
+      if (ended) return;
+      stop();
+      void invoke<void>('cancel_file_stream', { streamId: id });
+    };
+  },
+
   async onFileChange(cb) {
-    const unlisten = await listen<string>(FILE_CHANGED_EVENT, (event) => {
+    return fileService.onFileChanges((batch) => structuralPaths(batch).forEach(cb));
+  },
+
+  async onFileChanges(cb) {
+    const unlisten = await listen<FileChangeBatch>(FILE_CHANGES_EVENT, (event) => {
       cb(event.payload);
     });
     return () => {
@@ -62,6 +385,48 @@ export const fileService: FileService = {
     await invoke<void>('rename_file', { oldPath, newPath });
   },
 
+  async copyPaths(sources, destination, options = {}) {
+    return invoke<TransferredPath[]>('copy_paths', {
+      request: {
+        sources,
+        destination,
+        conflict: options.conflict,
+        operation_id: options.operationId,
+      },
+    });
+  },
+
+  async movePaths(sources, destination, options = {}) {
+    return invoke<TransferredPath[]>('move_paths', {
+      request: {
+        sources,
+        destination,
+        conflict: options.conflict,
+        operation_id: options.operationId,
+      },
+    });
+  },
+
+  async duplicatePath(path) {
+    return invoke<string>('duplicate_path', { path });
+  },
+
+  async onTransferProgress(cb) {
+    return listen<TransferProgress>('file-transfer-progress', (event) => cb(event.payload));
+  },
+
+  async undoFileOperation() {
+    return invoke<FileOperation | null>('file_journal_undo');
+  },
+
+  async redoFileOperation() {
+    return invoke<FileOperation | null>('file_journal_redo');
+  },
+
+  async getFileJournalStatus() {
+    return invoke<FileJournalStatus>('file_journal_status');
+  },
+
   async deleteFile(path, useTrash) {
     await invoke<void>('delete_file', { path, useTrash });
   },
@@ -74,10 +439,64 @@ export const fileService: FileService = {
     await invoke<void>('start_file_watcher');
   },
 
+  async stopFileWatcher() {
+    await invoke<void>('stop_file_watcher');
+  },
+
+  async restartFileWatcher() {
+    return invoke<WatcherStatus>('restart_file_watcher');
+  },
+
+  async retargetFileWatcher(roots) {
+    return invoke<WatcherStatus>('retarget_file_watcher', { roots });
+  },
+
+  async getWatcherStatus() {
+    return invoke<WatcherStatus>('file_watcher_status');
+  },
+
+  async configureWatcher(config) {
+    return invoke<WatcherStatus>('file_watcher_configure', { config });
+  },
+
+  async onWatcherStatus(cb) {
+    return listen<WatcherStatus>('file-watcher-status', (event) => cb(event.payload));
+  },
+
+  async onWatcherWarning(cb) {
+    return listen<string>('file-watcher-warning', (event) => cb(event.payload));
+  },
+
+  async quickOpen(query, limit) {
+    return invoke<QuickOpenMatch[]>('quick_open_query', {
+      request: { root: workspaceRoot || '.', query, limit },
+    });
+  },
+
+  async buildQuickOpenIndex(rebuild = false) {
+    return invoke<QuickOpenIndexInfo>('quick_open_index', { root: workspaceRoot || '.', rebuild });
+  },
+
   setWorkspaceRoot(root: string) {
     workspaceRoot = root || '.';
   },
 
+  async pickWorkspaceFolder(add = false) {
+    const result = await invoke<WorkspaceRoot[] | null>('workspace_pick_folder', { add });
+    if (result) workspaceRoot = result[0]?.path ?? '.';
+    return result;
+  },
+
+  async setWorkspaceRoots(roots) {
+    const result = await invoke<WorkspaceRoot[]>('workspace_set_roots', { roots });
+    workspaceRoot = result[0]?.path ?? '.';
+    return result;
+  },
+
+  async getWorkspaceRoots() {
+    return invoke<WorkspaceRoot[]>('workspace_get_roots');
+  },
+
   getWorkspaceRoot() {
     return workspaceRoot;
   },
@@ -0,0 +1,49 @@
+// src/lib/services/historyService.ts
+// -----------------------------------------------------------------------------
+// Обёртка над командами локальной истории файлов. Backend снимает снапшот при
+// каждом write_file (дедуп по хешу, срок хранения и лимит по размеру), сервис
+// отдаёт таймлайн файла, diff снапшота с текущим содержимым и восстановление.
+// -----------------------------------------------------------------------------
+
+import { invoke } from '@tauri-apps/api/core';
+import type { FileVersion } from './fileService';
+
+export type SnapshotSource = 'save' | 'external' | 'restore';
+
+export interface HistoryEntry {
+  id: string;
+  /** Миллисекунды с Unix epoch. */
+  timestamp: number;
+  size: number;
+  hash: string;
+  source: SnapshotSource;
+}
+
+/** Та же форма, что у GitDiff: показывается тем же diff-редактором. */
+export interface HistoryDiff {
+  path: string;
+  entry: HistoryEntry;
+  old_content: string;
+  new_content: string;
+  language: string;
+  binary: boolean;
+}
+
+export const historyService = {
+  /** Таймлайн файла, новые снапшоты первыми. */
+  async getTimeline(path: string): Promise<HistoryEntry[]> {
+    return invoke<HistoryEntry[]>('local_history_list', { path });
+  },
+
+  async diff(path: string, entryId: string): Promise<HistoryDiff> {
+    return invoke<HistoryDiff>('local_history_diff', { path, entryId });
+  },
+
+  /**
+   * Возвращает файл к снапшоту. Текущее содержимое перед этим тоже попадает в
+   * историю; результат — новая версия для `expected_version` открытого буфера.
+   */
+  async restore(path: string, entryId: string): Promise<FileVersion> {
+    return invoke<FileVersion>('local_history_restore', { path, entryId });
+  },
+};
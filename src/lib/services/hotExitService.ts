@@ -0,0 +1,73 @@
+// src/lib/services/hotExitService.ts
+// -----------------------------------------------------------------------------
+// Hot exit: периодически отправляет содержимое грязных буферов в backend, чтобы
+// после краша или закрытия приложения их можно было восстановить. Бэкап файла
+// удаляется backend'ом при write_file; для отменённых изменений и закрытых
+// untitled-буферов вызывается discard.
+// -----------------------------------------------------------------------------
+
+import { invoke } from '@tauri-apps/api/core';
+import type { FileVersion } from './fileService';
+
+export type BufferKey = { kind: 'file'; path: string } | { kind: 'untitled'; id: string };
+
+export interface DirtyBuffer {
+  key: BufferKey;
+  content: string;
+  language?: string | null;
+  /** Версия файла, из которой загружен буфер. */
+  version?: FileVersion | null;
+}
+
+export interface RestoredBuffer extends DirtyBuffer {
+  /** Мс с Unix epoch. */
+  timestamp: number;
+  /** Файл на диске изменился после загрузки буфера (или удалён). */
+  disk_changed: boolean;
+}
+
+export const DEFAULT_BACKUP_INTERVAL_MS = 5000;
+
+export const hotExitService = {
+  async backup(buffer: DirtyBuffer): Promise<void> {
+    await invoke<void>('hot_exit_backup', { request: buffer });
+  },
+
+  async discard(key: BufferKey): Promise<void> {
+    await invoke<void>('hot_exit_discard', { key });
+  },
+
+  /** Бэкапы прошлой сессии, старые первыми. Вызывается при запуске. */
+  async restore(): Promise<RestoredBuffer[]> {
+    return invoke<RestoredBuffer[]>('hot_exit_restore');
+  },
+
+  /**
+   * Запускает периодический бэкап. `collect` возвращает грязные буферы; уходят
+   * только те, что изменились с прошлого раунда. Возвращает функцию остановки.
+   */
+  startPeriodicBackup(
+    collect: () => DirtyBuffer[],
+    intervalMs = DEFAULT_BACKUP_INTERVAL_MS,
+  ): () => void {
+    const sent = new Map<string, string>();
+    let running = false;
+    const timer = setInterval(async () => {
+      if (running) return;
+      running = true;
+      try {
+        for (const buffer of collect()) {
+          const id = JSON.stringify(buffer.key);
+          if (sent.get(id) === buffer.content) continue;
+          await hotExitService.backup(buffer);
+          sent.set(id, buffer.content);
+        }
+      } catch (error) {
+        console.warn('[hotExit] backup failed', error);
+      } finally {
+        running = false;
+      }
+    }, intervalMs);
+    return () => clearInterval(timer);
+  },
+};
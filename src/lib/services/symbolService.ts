@@ -0,0 +1,91 @@
+// src/lib/services/symbolService.ts
+// -----------------------------------------------------------------------------
+// Обёртка над Tauri-командами индекса символов workspace. Индекс строится в
+// фоне и обновляется по событиям file watcher; сервис даёт fuzzy-поиск по
+// имени и виду символа для OutlineView и command palette.
+// -----------------------------------------------------------------------------
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { fileService } from './fileService';
+
+export type SymbolKind =
+  | 'function'
+  | 'method'
+  | 'class'
+  | 'struct'
+  | 'enum'
+  | 'interface'
+  | 'trait'
+  | 'type'
+  | 'module'
+  | 'constant'
+  | 'macro'
+  | 'heading';
+
+export interface WorkspaceSymbol {
+  name: string;
+  kind: SymbolKind;
+  /** Путь относительно корня workspace, через `/`. */
+  file: string;
+  /** 1-based строка и UTF-16 колонка имени символа. */
+  line: number;
+  column: number;
+  end_line: number;
+  end_column: number;
+  container_name: string | null;
+}
+
+export interface SymbolMatch extends WorkspaceSymbol {
+  score: number;
+  /** Совпавшие позиции в `name` (UTF-16 offsets) для подсветки. */
+  positions: number[];
+}
+
+export interface SymbolIndexStatus {
+  root: string;
+  indexing: boolean;
+  /** Почему последняя сборка или обновление индекса не удались. */
+  error: string | null;
+  file_count: number;
+  symbol_count: number;
+}
+
+export interface SymbolIndexProgress {
+  root: string;
+  files_indexed: number;
+  files_total: number;
+}
+
+export interface SymbolQueryOptions {
+  kinds?: SymbolKind[];
+  /** Ограничить поиск одним файлом (путь относительно корня). */
+  file?: string;
+  limit?: number;
+}
+
+const root = () => fileService.getWorkspaceRoot() || '.';
+
+export const symbolService = {
+  async startIndexing(rebuild = false): Promise<SymbolIndexStatus> {
+    return invoke<SymbolIndexStatus>('symbol_index_start', { root: root(), rebuild });
+  },
+
+  async getStatus(): Promise<SymbolIndexStatus> {
+    return invoke<SymbolIndexStatus>('symbol_index_status', { root: root() });
+  },
+
+  async query(query: string, options: SymbolQueryOptions = {}): Promise<SymbolMatch[]> {
+    return invoke<SymbolMatch[]>('symbol_query', {
+      request: { root: root(), query, ...options },
+    });
+  },
+
+  onProgress(handler: (progress: SymbolIndexProgress) => void): Promise<UnlistenFn> {
+    return listen<SymbolIndexProgress>('symbol-index-progress', (event) => handler(event.payload));
+  },
+
+  onStatus(handler: (status: SymbolIndexStatus) => void): Promise<UnlistenFn> {
+    return listen<SymbolIndexStatus>('symbol-index-status', (event) => handler(event.payload));
+  },
+};
@@ -19,7 +19,13 @@
 
 import { derived, writable, type Readable, get } from 'svelte/store';
 import type { FileNode } from '../types/fileNode';
-import { fileService } from '../services/fileService';
+import { fileService, isWriteConflict, type FileVersion } from '../services/fileService';
+import {
+  hotExitService,
+  type BufferKey,
+  type DirtyBuffer,
+  type RestoredBuffer,
+} from '../services/hotExitService';
 import {
   addTabToGroup,
   editorGroups,
@@ -30,7 +36,7 @@ import {
   reconcileGroupsWithOpenTabs,
   type EditorGroupId,
 } from './layout/editorGroupsStore';
-import { getWorkspaceFiles } from './workspaceStore';
+import { getWorkspaceFiles, workspaceStore } from './workspaceStore';
 
 // -----------------------------------------------------------------------------
 // Типы
@@ -66,6 +72,17 @@ const findFileById = (root: FileNode[], id: string): FileNode | null => {
   return null;
 };
 
+/** Дождаться загрузки дерева workspace: без него вкладку по пути не открыть. */
+const workspaceLoaded = (): Promise<void> =>
+  new Promise((resolve) => {
+    const unsubscribe = workspaceStore.subscribe((state) => {
+      if (state.loading) return;
+      // Колбэк может сработать синхронно, до возврата subscribe.
+      queueMicrotask(() => unsubscribe());
+      resolve();
+    });
+  });
+
 const detectLanguage = (name: string): LanguageId | string => {
   if (name.endsWith('.svelte')) return 'svelte';
   if (name.endsWith('.ts')) return 'ts';
@@ -87,6 +104,33 @@ const createEditorStore = (filesTreeProvider: () => FileNode[]) => {
     activeEditorId: null,
   });
 
+  /**
+   * Версии файлов на диске, с которыми работают открытые вкладки (по id).
+   * Уходят в write_file как `expected_version`, чтобы не затереть чужие
+   * изменения файла.
+   */
+  const fileVersions = new Map<string, FileVersion>();
+
+  /** Несохранённое содержимое грязных вкладок (по id) — для hot exit бэкапов. */
+  const bufferContents = new Map<string, string>();
+
+  /** Буферы прошлой сессии, которые подставляются вместо файла при загрузке вкладки. */
+  const restoredBuffers = new Map<string, RestoredBuffer>();
+
+  /** Вкладка без файла в workspace — untitled-буфер. */
+  const bufferKey = (tab: EditorTab): BufferKey =>
+    findFileById(filesTreeProvider(), tab.id)
+      ? { kind: 'file', path: tab.path }
+      : { kind: 'untitled', id: tab.id };
+
+  /** Бэкап больше не нужен: изменения вкладки отброшены. */
+  const discardBackup = (tab: EditorTab): void => {
+    bufferContents.delete(tab.id);
+    hotExitService
+      .discard(bufferKey(tab))
+      .catch((error) => console.warn('[hotExit] discard failed', error));
+  };
+
   /**
    * Найти таб по id.
    */
@@ -236,11 +280,13 @@ const createEditorStore = (filesTreeProvider: () => FileNode[]) => {
   const closeEditor = (fileId: string) => {
     let latestState: EditorState | null = null;
     let removed = false;
+    let closedTab: EditorTab | null = null;
 
     update((state) => {
       const idx = state.openTabs.findIndex((t) => t.id === fileId);
       if (idx === -1) return state;
       removed = true;
+      closedTab = state.openTabs[idx];
 
       const newTabs = [...state.openTabs.slice(0, idx), ...state.openTabs.slice(idx + 1)];
 
@@ -269,6 +315,11 @@ const createEditorStore = (filesTreeProvider: () => FileNode[]) => {
 
     const closedState: EditorState = latestState;
 
+    // Закрытая грязная вкладка (в т.ч. untitled) теряет изменения — и их бэкап.
+    const closed = closedTab as EditorTab | null;
+    if (closed?.isDirty) discardBackup(closed);
+    fileVersions.delete(fileId);
+    restoredBuffers.delete(fileId);
     removeTabFromGroups(fileId);
     reconcileGroupsWithOpenTabs(closedState.openTabs.map((t) => t.id));
 
@@ -287,11 +338,7 @@ const createEditorStore = (filesTreeProvider: () => FileNode[]) => {
     }
   };
 
-  /**
-   * Пометить вкладку как "грязную" (есть несохранённые изменения) или чистую.
-   * Используется EditorCore.onDidChangeModelContent и логикой сохранения.
-   */
-  const markDirty = (id: string, isDirty: boolean): void => {
+  const setDirty = (id: string, isDirty: boolean): void => {
     update((state) => ({
       ...state,
       openTabs: state.openTabs.map((tab) => (tab.id === id ? { ...tab, isDirty } : tab)),
@@ -299,14 +346,120 @@ const createEditorStore = (filesTreeProvider: () => FileNode[]) => {
   };
 
   /**
-   * Persist editor content to the mock file registry and reset the dirty flag.
+   * Пометить вкладку как "грязную" (есть несохранённые изменения) или чистую.
+   * Чистой без сохранения вкладка становится при откате изменений, поэтому
+   * её hot exit бэкап удаляется.
+   */
+  const markDirty = (id: string, isDirty: boolean): void => {
+    const tab = findTab(get({ subscribe }), id);
+    if (!isDirty && tab?.isDirty) discardBackup(tab);
+    setDirty(id, isDirty);
+  };
+
+  /**
+   * Правка содержимого вкладки (EditorCore.onDidChangeModelContent):
+   * вкладка становится грязной, содержимое уходит в следующий hot exit бэкап.
+   */
+  const recordEdit = (id: string, value: string): void => {
+    bufferContents.set(id, value);
+    setDirty(id, true);
+  };
+
+  /**
+   * Прочитать файл для вкладки и запомнить версию, с которой она работает.
+   * Для восстановленной вкладки вместо файла отдаётся её буфер.
+   */
+  const loadContent = async (id: string, path: string): Promise<string> => {
+    const restored = restoredBuffers.get(id);
+    if (restored) {
+      restoredBuffers.delete(id);
+      // Версия, с которой редактировался буфер: изменения на диске с тех пор
+      // всплывут как конфликт при сохранении.
+      if (restored.version) fileVersions.set(id, restored.version);
+      return restored.content;
+    }
+    const file = await fileService.readTextFile(path);
+    fileVersions.set(id, file.version);
+    return file.content;
+  };
+
+  /**
+   * Сохранить содержимое вкладки и сбросить флаг "грязности".
+   * Если файл изменился на диске после загрузки, перезапись подтверждает
+   * пользователь; при отказе вкладка остаётся грязной, а ошибка `conflict`
+   * пробрасывается вызывающему. Бэкап файла backend удаляет сам.
    */
   const updateContent = async (id: string, value: string): Promise<void> => {
     const fileNode = findFileById(filesTreeProvider(), id);
     if (!fileNode || fileNode.type !== 'file') return;
 
-    await fileService.writeFile(fileNode.path, value);
-    markDirty(id, false);
+    let version: FileVersion;
+    try {
+      version = await fileService.writeFile(fileNode.path, value, {
+        expected_version: fileVersions.get(id),
+      });
+    } catch (error) {
+      if (
+        !isWriteConflict(error) ||
+        !window.confirm(`${fileNode.name} changed on disk. Overwrite it with your changes?`)
+      ) {
+        throw error;
+      }
+      version = await fileService.writeFile(fileNode.path, value);
+    }
+    fileVersions.set(id, version);
+    bufferContents.delete(id);
+    setDirty(id, false);
+  };
+
+  /** Грязные вкладки с известным содержимым — для периодического бэкапа. */
+  const collectDirtyBuffers = (): DirtyBuffer[] =>
+    get({ subscribe })
+      .openTabs.filter((tab) => tab.isDirty && bufferContents.has(tab.id))
+      .map((tab) => ({
+        key: bufferKey(tab),
+        content: bufferContents.get(tab.id) as string,
+        language: tab.language,
+        version: fileVersions.get(tab.id) ?? null,
+      }));
+
+  /**
+   * Предложить восстановить буферы прошлой сессии. Принятые открываются
+   * грязными вкладками с содержимым бэкапа, отклонённые удаляются.
+   */
+  const offerRestore = async (): Promise<void> => {
+    const restored = await hotExitService.restore();
+    if (restored.length === 0) return;
+    await workspaceLoaded();
+
+    const changed = restored.filter((buffer) => buffer.disk_changed).length;
+    const accepted = window.confirm(
+      `Restore ${restored.length} unsaved editor(s) from the last session?` +
+        (changed > 0 ? ` ${changed} of them changed on disk since.` : ''),
+    );
+    for (const buffer of restored) {
+      // Буферы, которые не открыть вкладкой (untitled — редактор их пока не
+      // создаёт, удалённый файл), остаются до отказа от восстановления.
+      const tab =
+        accepted && buffer.key.kind === 'file'
+          ? ensureTabForFile(buffer.key.path, { activate: false })
+          : null;
+      if (!tab) {
+        if (!accepted) await hotExitService.discard(buffer.key);
+        continue;
+      }
+      restoredBuffers.set(tab.id, buffer);
+      recordEdit(tab.id, buffer.content);
+    }
+  };
+
+  /**
+   * Hot exit: предлагает восстановить прошлую сессию и запускает
+   * периодический бэкап грязных вкладок. Возвращает функцию остановки.
+   */
+  const startHotExit = (): (() => void) => {
+    offerRestore().catch((error) => console.warn('[hotExit] restore failed', error));
+    return hotExitService.startPeriodicBackup(collectDirtyBuffers);
   };
 
   /**
@@ -349,7 +502,10 @@ const createEditorStore = (filesTreeProvider: () => FileNode[]) => {
     setActiveEditor,
     closeEditor,
     markDirty,
+    recordEdit,
+    loadContent,
     updateContent,
+    startHotExit,
     ensureTabForFile,
   };
 };
@@ -424,7 +580,7 @@ export function activeTabVisibleForGroup(groupId: EditorGroupId): Readable<boole
 // - editorStore: единый источник правды по EditorTab и isDirty.
 // - editorGroupsStore: управляет только раскладкой вкладок по группам/сплитам.
 // - Связка:
-//   - EditorCore -> onDidChangeModelContent -> editorStore.markDirty(...);
+//   - EditorCore -> onDidChangeModelContent -> editorStore.recordEdit(...);
 //   - editorStore хранит логические сущности вкладок;
 //   - editorGroupsStore оперирует только id вкладок.
 // -----------------------------------------------------------------------------
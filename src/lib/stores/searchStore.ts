@@ -9,14 +9,78 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { writable, get, type Readable } from 'svelte/store';
 
+/** Zero-based, end-exclusive match offsets. */
+export interface HitRange {
+  start: number;
+  end: number;
+}
+
 export interface SearchHit {
   file: string;
   line: number;
+  /** 1-based column in UTF-16 code units, directly usable by Monaco. */
   column: number;
+  end_line: number;
+  end_column: number;
+  byte_range: HitRange;
+  char_range: HitRange;
+  utf16_range: HitRange;
   match_text: string;
   line_text: string;
+  context_before: string[];
+  context_after: string[];
+}
+
+/** One line of a replace preview, before and after replacing. */
+export interface ReplacePreviewLine {
+  line: number;
+  original: string;
+  replaced: string;
+  match_count: number;
+}
+
+/**
+ * Replace preview of one file, by path relative to the search root.
+ * `content_hash` ties it to the contents the preview was computed from.
+ */
+export interface FileReplacePreview {
+  file: string;
+  content_hash: string;
+  lines: ReplacePreviewLine[];
+}
+
+/** A previewed file to apply; with `lines` set only those lines are rewritten. */
+export interface ReplaceFileSelection {
+  file: string;
+  content_hash: string;
+  lines?: number[];
+}
+
+export interface ReplaceSummary {
+  files_changed: number;
+  lines_changed: number;
+  replacements: number;
+}
+
+interface SearchEvent {
+  search_id: string;
 }
 
+interface SearchHitsEvent extends SearchEvent {
+  hits: SearchHit[];
+}
+
+interface SearchProgressEvent extends SearchEvent {
+  files_scanned: number;
+  files_total: number;
+}
+
+interface SearchErrorEvent extends SearchEvent {
+  message: string;
+}
+
+type ReplacePreviewEvent = SearchEvent & FileReplacePreview;
+
 export interface InlineSearchState {
   query: string;
   replaceText: string;
@@ -27,13 +91,41 @@ export interface InlineSearchState {
   visible: boolean;
 }
 
+export interface ProjectSearchOptions {
+  caseSensitive?: boolean;
+  useRegex?: boolean;
+  /** Glob patterns a file must match, e.g. `*.ts` or `src/lib/**`. */
+  include?: string[];
+  /** Glob patterns skipped in addition to .gitignore rules. */
+  exclude?: string[];
+  /** Context lines reported before and after each hit. */
+  contextBefore?: number;
+  contextAfter?: number;
+  /** Match across line boundaries, e.g. `impl Foo \{\n\s+fn`. */
+  multiline?: boolean;
+}
+
+export interface ReplaceOptions extends ProjectSearchOptions {
+  /** Adapt each replacement to the casing of the text it replaces. */
+  preserveCase?: boolean;
+}
+
 export interface ProjectSearchState {
   query: string;
   caseSensitive: boolean;
   useRegex: boolean;
   isSearching: boolean;
   results: SearchHit[];
+  /** Replacement text of the current replace preview; `null` for a plain search. */
+  replaceText: string | null;
+  /** Files the replace preview would change, filled by `search-replace-preview` events. */
+  replacePreviews: FileReplacePreview[];
+  isApplying: boolean;
+  /** Outcome of the last applied replace. */
+  lastReplace: ReplaceSummary | null;
   error: string | null;
+  filesScanned: number;
+  filesTotal: number;
 }
 
 type InlineSearchStore = Readable<InlineSearchState> & {
@@ -53,8 +145,17 @@ type ProjectSearchStore = Readable<ProjectSearchState> & {
   search(
     root: string,
     query: string,
-    options?: { caseSensitive?: boolean; useRegex?: boolean }
+    options?: ProjectSearchOptions
+  ): Promise<void>;
+  /** Starts a search in replace mode: previews arrive instead of hits. */
+  previewReplace(
+    root: string,
+    query: string,
+    replace: string,
+    options?: ReplaceOptions
   ): Promise<void>;
+  /** Applies the current preview; by default to every previewed file. */
+  applyReplace(files?: ReplaceFileSelection[]): Promise<ReplaceSummary | null>;
   cancel(): Promise<void>;
   clear(): void;
   dispose(): void;
@@ -78,7 +179,13 @@ const projectInitialState: ProjectSearchState = {
   useRegex: false,
   isSearching: false,
   results: [],
+  replaceText: null,
+  replacePreviews: [],
+  isApplying: false,
+  lastReplace: null,
   error: null,
+  filesScanned: 0,
+  filesTotal: 0,
 };
 
 function createInlineSearchStore(): InlineSearchStore {
@@ -122,80 +229,193 @@ function createInlineSearchStore(): InlineSearchStore {
 function createProjectSearchStore(): ProjectSearchStore {
   const { subscribe, update, set } = writable<ProjectSearchState>(projectInitialState);
   let listeners: UnlistenFn[] = [];
+  // Id of the search this store is showing. Events of other searches (or of
+  // a search this store already replaced) are ignored.
+  let activeSearchId: string | null = null;
+  let searchCounter = 0;
+  const storeId = Math.random().toString(36).slice(2);
+  // Request of the replace preview being shown; `applyReplace` repeats it.
+  let replaceRequest: {
+    root: string;
+    query: string;
+    replace: string;
+    options?: ReplaceOptions;
+  } | null = null;
+
+  const isActive = (searchId: string) => searchId === activeSearchId;
 
   async function setupListeners(): Promise<void> {
-    const hitListener = await listen<SearchHit>('search-hit', (event) => {
-      update((state) => ({ ...state, results: [...state.results, event.payload] }));
+    const hitsListener = await listen<SearchHitsEvent>('search-hits', (event) => {
+      if (!isActive(event.payload.search_id)) return;
+      update((state) => ({ ...state, results: [...state.results, ...event.payload.hits] }));
+    });
+    listeners.push(hitsListener);
+
+    const previewListener = await listen<ReplacePreviewEvent>(
+      'search-replace-preview',
+      (event) => {
+        if (!isActive(event.payload.search_id)) return;
+        const { file, content_hash, lines } = event.payload;
+        update((state) => ({
+          ...state,
+          replacePreviews: [...state.replacePreviews, { file, content_hash, lines }],
+        }));
+      }
+    );
+    listeners.push(previewListener);
+
+    const progressListener = await listen<SearchProgressEvent>('search-progress', (event) => {
+      if (!isActive(event.payload.search_id)) return;
+      const { files_scanned, files_total } = event.payload;
+      update((state) => ({ ...state, filesScanned: files_scanned, filesTotal: files_total }));
     });
-    listeners.push(hitListener);
+    listeners.push(progressListener);
 
-    const completeListener = await listen<number>('search-complete', () => {
+    const completeListener = await listen<SearchEvent>('search-complete', (event) => {
+      if (!isActive(event.payload.search_id)) return;
       update((state) => ({ ...state, isSearching: false }));
     });
     listeners.push(completeListener);
 
-    const cancelListener = await listen('search-cancelled', () => {
+    const cancelListener = await listen<SearchEvent>('search-cancelled', (event) => {
+      if (!isActive(event.payload.search_id)) return;
       update((state) => ({ ...state, isSearching: false }));
     });
     listeners.push(cancelListener);
 
-    const errorListener = await listen<string>('search-error', (event) => {
-      update((state) => ({ ...state, error: event.payload, isSearching: false }));
+    const errorListener = await listen<SearchErrorEvent>('search-error', (event) => {
+      if (!isActive(event.payload.search_id)) return;
+      update((state) => ({ ...state, error: event.payload.message, isSearching: false }));
     });
     listeners.push(errorListener);
   }
 
   void setupListeners();
 
+  async function start(
+    root: string,
+    query: string,
+    options: ReplaceOptions | undefined,
+    replace: string | null
+  ): Promise<void> {
+    if (!query.trim()) {
+      update((state) => ({ ...state, error: 'Search query cannot be empty' }));
+      return;
+    }
+
+    set({
+      ...projectInitialState,
+      query,
+      caseSensitive: options?.caseSensitive ?? false,
+      useRegex: options?.useRegex ?? false,
+      isSearching: true,
+      replaceText: replace,
+    });
+
+    searchCounter += 1;
+    const searchId = `${storeId}-${searchCounter}`;
+    activeSearchId = searchId;
+
+    try {
+      await invoke('search_files', {
+        request: {
+          search_id: searchId,
+          root,
+          query,
+          use_regex: options?.useRegex ?? false,
+          case_sensitive: options?.caseSensitive ?? false,
+          include: options?.include ?? [],
+          exclude: options?.exclude ?? [],
+          context_before: options?.contextBefore ?? 0,
+          context_after: options?.contextAfter ?? 0,
+          multiline: options?.multiline ?? false,
+          replace,
+          preserve_case: options?.preserveCase ?? false,
+        },
+      });
+    } catch (error) {
+      update((state) => ({
+        ...state,
+        error: error instanceof Error ? error.message : String(error),
+        isSearching: false,
+      }));
+    }
+  }
+
   return {
     subscribe,
     async search(
       root: string,
       query: string,
-      options?: { caseSensitive?: boolean; useRegex?: boolean }
+      options?: ProjectSearchOptions
     ) {
-      if (!query.trim()) {
-        update((state) => ({ ...state, error: 'Search query cannot be empty' }));
-        return;
-      }
+      replaceRequest = null;
+      await start(root, query, options, null);
+    },
+    async previewReplace(
+      root: string,
+      query: string,
+      replace: string,
+      options?: ReplaceOptions
+    ) {
+      replaceRequest = { root, query, replace, options };
+      await start(root, query, options, replace);
+    },
+    async applyReplace(files?: ReplaceFileSelection[]) {
+      const current = get({ subscribe });
+      const request = replaceRequest;
+      if (!request || current.isSearching || current.isApplying) return null;
 
-      set({
-        query,
-        caseSensitive: options?.caseSensitive ?? false,
-        useRegex: options?.useRegex ?? false,
-        isSearching: true,
-        results: [],
-        error: null,
-      });
+      const selection =
+        files ??
+        current.replacePreviews.map(({ file, content_hash }) => ({ file, content_hash }));
+      if (selection.length === 0) return null;
 
+      update((state) => ({ ...state, isApplying: true, error: null }));
       try {
-        await invoke('search_files', {
+        const summary = await invoke<ReplaceSummary>('search_replace_apply', {
           request: {
-            root,
-            query,
-            use_regex: options?.useRegex ?? false,
-            case_sensitive: options?.caseSensitive ?? false,
+            root: request.root,
+            query: request.query,
+            replace: request.replace,
+            use_regex: request.options?.useRegex ?? false,
+            case_sensitive: request.options?.caseSensitive ?? false,
+            preserve_case: request.options?.preserveCase ?? false,
+            files: selection,
           },
         });
+        // The files changed, so the preview no longer describes them.
+        const applied = new Set(selection.map((s) => s.file));
+        update((state) => ({
+          ...state,
+          isApplying: false,
+          lastReplace: summary,
+          replacePreviews: state.replacePreviews.filter((p) => !applied.has(p.file)),
+        }));
+        return summary;
       } catch (error) {
+        // E.g. a file changed on disk since the preview; nothing was written then.
         update((state) => ({
           ...state,
+          isApplying: false,
           error: error instanceof Error ? error.message : String(error),
-          isSearching: false,
         }));
+        return null;
       }
     },
     async cancel() {
       const current = get({ subscribe });
-      if (!current.isSearching) return;
+      if (!current.isSearching || !activeSearchId) return;
       try {
-        await invoke('cancel_search');
+        await invoke('cancel_search', { searchId: activeSearchId });
         update((state) => ({ ...state, isSearching: false }));
       } catch (error) {
         console.error('Failed to cancel search:', error);
       }
     },
     clear() {
+      activeSearchId = null;
+      replaceRequest = null;
       set(projectInitialState);
     },
     dispose() {
@@ -203,6 +423,8 @@ function createProjectSearchStore(): ProjectSearchStore {
         unlisten();
       }
       listeners = [];
+      activeSearchId = null;
+      replaceRequest = null;
       set(projectInitialState);
     },
     toggleCaseSensitive() {
@@ -1,6 +1,6 @@
 import { writable } from 'svelte/store';
 import type { FileNode } from '../types/fileNode';
-import { fileService } from '../services/fileService';
+import { fileService, structuralPaths, type FileChangeBatch } from '../services/fileService';
 
 /**
  * Хранилище workspace-файлов, построенное на Tauri-командах.
@@ -25,12 +25,13 @@ const deriveWorkspaceName = (root: string | null): string => {
   return segments.at(-1) || 'Workspace';
 };
 
-let currentRoot: string | null = fileService.getWorkspaceRoot() || '.';
+// Backend стартует без корней: пока пользователь не откроет папку, доступа нет.
+let currentRoot: string | null = null;
 
 const internal = writable<WorkspaceState>({
   name: deriveWorkspaceName(currentRoot),
   files: [],
-  loading: true,
+  loading: false,
   root: currentRoot,
 });
 
@@ -73,18 +74,47 @@ const loadWorkspaceFiles = async () => {
 
 let unsubscribeWatcher: (() => void) | null = null;
 
+const collectPaths = (nodes: FileNode[], into: Set<string>): Set<string> => {
+  for (const node of nodes) {
+    into.add(node.path);
+    if (node.children) collectPaths(node.children, into);
+  }
+  return into;
+};
+
+/**
+ * Дерево перечитываем только при структурных изменениях. `changed` для
+ * неизвестного пути — это новый файл, записанный атомарно (temp + rename).
+ */
+const affectsTree = (batch: FileChangeBatch): boolean => {
+  if (structuralPaths(batch).length > 0) return true;
+  if (batch.changed.length === 0) return false;
+  const known = collectPaths(getWorkspaceFiles(), new Set());
+  return batch.changed.some((path) => !known.has(path));
+};
+
 const setupWatcher = async () => {
   if (unsubscribeWatcher) {
     unsubscribeWatcher();
   }
-  unsubscribeWatcher = await fileService.onFileChange(() => {
-    void loadWorkspaceFiles();
+  unsubscribeWatcher = await fileService.onFileChanges((batch) => {
+    if (affectsTree(batch)) {
+      void loadWorkspaceFiles();
+    }
   });
 };
 
+/** Подхватывает корни, уже открытые в backend (например, после перезагрузки webview). */
+const syncWorkspaceRoot = async () => {
+  const roots = await fileService.getWorkspaceRoots();
+  currentRoot = roots[0]?.path ?? null;
+  if (currentRoot) fileService.setWorkspaceRoot(currentRoot);
+  await loadWorkspaceFiles();
+};
+
 void setupWatcher();
 void fileService.startFileWatcher();
-void loadWorkspaceFiles();
+void syncWorkspaceRoot();
 
 export const stopWorkspaceWatching = () => {
   if (unsubscribeWatcher) {
@@ -96,13 +126,19 @@ export const stopWorkspaceWatching = () => {
 export const workspaceStore = {
   subscribe: internal.subscribe,
   refresh: loadWorkspaceFiles,
-  openFolder: (root: string) => {
-    currentRoot = root;
-    fileService.setWorkspaceRoot(root);
+  /**
+   * Папку выбирает пользователь в нативном диалоге backend: webview не может
+   * сам назначить корень. Backend перезапускает watcher по новому набору корней.
+   */
+  openFolder: async () => {
+    const roots = await fileService.pickWorkspaceFolder();
+    if (!roots) return;
+    currentRoot = roots[0]?.path ?? null;
     void loadWorkspaceFiles();
   },
-  closeFolder: () => {
+  closeFolder: async () => {
     currentRoot = null;
+    await fileService.setWorkspaceRoots([]);
     void loadWorkspaceFiles();
   },
   getWorkspaceRoot: (): string | null => {
@@ -15,4 +15,20 @@ export interface FileNode {
   size?: number;
   modified?: number;
   children?: FileNode[];
+  /** Есть ли у директории содержимое (без его чтения). */
+  has_children?: boolean;
+  /** Цель symlink, если узел является ссылкой. */
+  symlink_target?: string | null;
+  /** Исключён правилами .gitignore/.ignore. */
+  ignored?: boolean;
+}
+
+/** Страница листинга директории (`read_directory`). */
+export interface DirectoryPage {
+  /** Путь директории относительно корня (`.` для корня). */
+  path: string;
+  entries: FileNode[];
+  offset: number;
+  total: number;
+  next_offset: number | null;
 }